@@ -1,3 +1,16 @@
+// CSRF
+
+// Returns the session's CSRF token, the server rejects changes that don't send it back
+function csrfToken() {
+    var match = document.cookie.match(/(?:^|; )csrfToken=([^;]*)/);
+    return match ? decodeURIComponent(match[1]) : '';
+}
+
+// Echoes the CSRF token on every htmx request
+document.addEventListener('htmx:configRequest', function (event) {
+    event.detail.headers['X-CSRF-Token'] = csrfToken();
+});
+
 htmx.on('startButton', 'htmx:afterRequest', function () {
     document.getElementById('startButton').classList.add('selected');
     document.getElementById('stopButton').classList.remove('selected');
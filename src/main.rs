@@ -1,15 +1,19 @@
-#[forbid(unsafe_code)]
+#![forbid(unsafe_code)]
 // Brings the axum backend into scope
 use axum::{
     body::Body,
-    extract::Multipart,
+    extract::{ConnectInfo, DefaultBodyLimit, Extension, Multipart, Request},
     http::Response,
+    middleware::{self, Next},
     response::{Html, IntoResponse, Redirect},
-    routing::{get, head, post, put},
-    Form, Router,
+    routing::{delete, get, head, post, put},
+    Form, Json, Router,
 };
 
-use axum_extra::extract::{cookie::Cookie, CookieJar};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
 
 // Bring the cryptography library into scope
 use argon2::{
@@ -26,16 +30,22 @@ use uuid::Uuid;
 use lazy_static::lazy_static;
 use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex, Notify, Semaphore};
 
 use std::path::Path;
 
+// Brings libraries needed for login rate limiting into scope
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Instant;
+
 // Brings libraries needed for the server headers into scope
+use axum::http::{uri::Authority, HeaderMap, Uri};
+use axum_server::tls_rustls::RustlsConfig;
 use hyper::{
-    header::{CONTENT_TYPE, SET_COOKIE},
+    header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, HOST, IF_NONE_MATCH, RETRY_AFTER, SET_COOKIE},
     StatusCode,
 };
-use mime::IMAGE_PNG;
 use mime::TEXT_CSS;
 use mime::TEXT_JAVASCRIPT;
 
@@ -45,16 +55,54 @@ use serde::{Deserialize, Serialize};
 // Brings standard libraries needed for many things into scope
 use std::io::{self, BufRead};
 
-// Used for sponsor roll
 use base64::prelude::*;
 
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+// Brings the template engine for themes into scope
+use minijinja::Environment;
+
+// Used to render the scorebug to a png
+use resvg::{tiny_skia, usvg, usvg::fontdb};
+
+// Used to address cached images by their contents
+use sha2::{Digest, Sha256};
+
+use rand::{thread_rng, Rng, RngCore};
 
 // Other async imports
 use tokio::time::sleep;
 use tokio::time::Duration;
 
-const CONFIG_FILE: &'static str = "config.cfg"; // Sets the name of the config file
+// Brings the logging libraries into scope
+use tracing::{debug, error, info, warn, Level};
+use tracing_subscriber::{filter::Targets, fmt, prelude::*, reload, EnvFilter, Registry};
+
+const CONFIG_FILE: &str = "config.cfg"; // Sets the name of the config file
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n"; // Every PNG file starts with these bytes
+const SECURITY_LOG_FILE: &str = "login/security.log"; // Sets the name of the security log
+const SECURITY_LOG_TARGET: &str = "security"; // Log events with this target also go to the security log
+const DEFAULT_LOG_LEVEL: &str = "info"; // Used when neither RUST_LOG nor log_level is set
+const SECRET_FILE: &str = "login/secrets.txt"; // Sets the name of the JWT signing key file
+const SECRET_LEN: usize = 64; // Length of the JWT signing key in bytes (HS256 wants at least 32)
+const CSRF_COOKIE: &str = "csrfToken"; // Readable by the dashboard's scripts, which echo it back
+const CSRF_HEADER: &str = "X-CSRF-Token"; // Header mutating requests have to carry the CSRF token in
+const LOGIN_BACKOFF_MAX_SECS: u64 = 60; // Caps the delay between failed login attempts
+const LOGIN_VERIFY_TIMEOUT_SECS: u64 = 10; // A password check that hasn't finished after this long no longer blocks new attempts
+const SPONSOR_MANIFEST_NAME: &str = "sponsors.json"; // Keeps the order of the sponsors in ./sponsors and which are enabled
+const SPONSOR_IMPRESSIONS_FILE: &str = "sponsor_impressions.json"; // Seconds each sponsor was on air, for billing
+const MATCHES_FILE: &str = "matches.json"; // Saves the matches, each is a home and an away team of the library
+const COUNTDOWNS_FILE: &str = "countdowns.json"; // Saves the named countdowns and which one is selected
+const POPUPS_FILE: &str = "popups.json"; // Saves the popups added through the API
+const LOWER_THIRDS_FILE: &str = "lower_thirds.json"; // Saves the lower third library, their images are in ./lower_thirds
+const SCENES_FILE: &str = "scenes.json"; // Saves the overlay scenes and which one is on air
+const TEMPLATES_DIR: &str = "templates"; // Holds a folder of overlay templates for every theme
+const MAX_QUEUED_POPUPS: usize = 20; // Popups queued beyond this are dropped
+const SPONSOR_WINDOW_FORMAT: &str = "%Y-%m-%dT%H:%M"; // Format of the active window of sponsors, in local time
+const SPONSOR_MAX_DURATION_SECS: u64 = 3600; // Longest a sponsor stays on screen each time it is shown
+const SPONSOR_MAX_WEIGHT: u32 = 100; // Highest weight of a sponsor in the sponsor roll
+const RENDER_MAX_WIDTH: u32 = 3840; // Largest scorebug that is rendered, a 4k frame
+const RENDER_MAX_HEIGHT: u32 = 2160; // Tallest scorebug that is rendered
+const RENDER_MAX_CONCURRENT: usize = 2; // Scorebug pngs rendered at the same time, other requests wait for their turn
+const RENDER_GAP: f64 = 0.08; // Space around the boxes of a rendered scorebug, relative to its height
 
 // Declares and intializes all the global variables used everywhere in the app
 lazy_static! {
@@ -66,26 +114,73 @@ lazy_static! {
     static ref TIME_SECS: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
     static ref TIME_STARTED: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
     static ref CHROMAKEY: Arc<Mutex<(u8, u8, u8)>> = Arc::new(Mutex::new((0, 0, 0)));
+    static ref TRANSPARENT_OVERLAY: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    static ref RENDER_WIDTH: Arc<Mutex<u32>> = Arc::new(Mutex::new(1280));
+    static ref RENDER_HEIGHT: Arc<Mutex<u32>> = Arc::new(Mutex::new(72));
+    static ref RENDER_FONT: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    static ref RENDER_FONTS: Arc<Mutex<Arc<fontdb::Database>>> =
+        Arc::new(Mutex::new(Arc::new(fontdb::Database::new())));
+    static ref RENDER_PERMITS: Semaphore = Semaphore::new(RENDER_MAX_CONCURRENT);
+    // The last rendered png with the svg it was rendered from, the svg includes the size and the whole game state
+    static ref RENDER_CACHE: Arc<Mutex<Option<(String, axum::body::Bytes)>>> = Arc::new(Mutex::new(None));
     static ref QUARTER: Arc<Mutex<u8>> = Arc::new(Mutex::new(1));
     static ref SHOW_QUARTER: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
     static ref ADDR: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("")));
     static ref SHOW_SPONSOR: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref LAST_SPONSOR: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
+    // The sponsor on screen, published by the sponsor roll ticker
+    static ref CURRENT_SPONSOR: watch::Sender<Option<Sponsor>> = watch::channel(None).0;
+    // Wakes the sponsor roll ticker when the roll is shown or hidden and when the sponsors change
+    static ref SPONSOR_ROLL_WAKE: Notify = Notify::new();
     static ref SHOW_COUNTDOWN: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref COUNTDOWN_STARTED: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref COUNTDOWN_MINS: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
-    static ref COUNTDOWN_SECS: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
-    static ref COUNTDOWN_TITLE: Arc<Mutex<String>> =
-        Arc::new(Mutex::new(String::from("countdown")));
-    static ref SPONSOR_IMG_TAGS: Arc<Mutex<Vec<Html<String>>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref COUNTDOWNS: Arc<Mutex<Vec<Countdown>>> = Arc::new(Mutex::new(default_countdowns()));
+    static ref ACTIVE_COUNTDOWN: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("default")));
+    // Popups from the config (and the built in ones) and popups added through the API
+    static ref CONFIG_POPUPS: Arc<Mutex<Vec<PopupDef>>> = Arc::new(Mutex::new(builtin_popups()));
+    static ref API_POPUPS: Arc<Mutex<Vec<PopupDef>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref POPUP_QUEUE: Arc<Mutex<Vec<QueuedPopup>>> = Arc::new(Mutex::new(Vec::new()));
+    // The popup on screen, set by the popup ticker
+    static ref CURRENT_POPUP: Arc<Mutex<Option<QueuedPopup>>> = Arc::new(Mutex::new(None));
+    // Wakes the popup ticker when a popup is queued or the popups are cleared
+    static ref POPUP_WAKE: Notify = Notify::new();
+    static ref THEME: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("default")));
+    static ref SCENES: Arc<Mutex<Vec<Scene>>> = Arc::new(Mutex::new(default_scenes()));
+    static ref ACTIVE_SCENE: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("in_game")));
+    static ref LOWER_THIRDS: Arc<Mutex<Vec<LowerThird>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref ON_AIR_LOWER_THIRD: Arc<Mutex<Option<OnAirLowerThird>>> = Arc::new(Mutex::new(None));
+    // Wakes the lower third ticker when a lower third is taken or cleared
+    static ref LOWER_THIRD_WAKE: Notify = Notify::new();
+    static ref IMAGE_CACHE: Arc<Mutex<ImageCache>> = Arc::new(Mutex::new(ImageCache::default()));
+    static ref SPONSORS: Arc<Mutex<Vec<Sponsor>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref SPONSOR_IMPRESSIONS: Arc<Mutex<HashMap<String, SponsorImpressions>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref SPONSOR_DIR_STATE: Arc<Mutex<SponsorDirState>> = Arc::new(Mutex::new(Vec::new()));
     static ref HOME_IMG_DATA: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
     static ref AWAY_IMG_DATA: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
-    static ref SECRET: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
-    static ref TIMEOUT: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref FOUL_HOME: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref FOUL_AWAY: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-    static ref FLAG: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    static ref MATCHES: Arc<Mutex<Vec<SavedMatch>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref HOME_TEAM_ID: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    static ref AWAY_TEAM_ID: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    static ref HOME_COLORS: Arc<Mutex<TeamColors>> = Arc::new(Mutex::new(TeamColors::default()));
+    static ref AWAY_COLORS: Arc<Mutex<TeamColors>> = Arc::new(Mutex::new(TeamColors::default()));
+    static ref SECRET: Arc<Mutex<Redacted<Vec<u8>>>> = Arc::new(Mutex::new(Redacted::default()));
+    static ref GAME_EVENTS: Arc<Mutex<Vec<GameEvent>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref GAME_STARTED_AT: Arc<Mutex<Option<u64>>> = Arc::new(Mutex::new(None));
     static ref SECURE_AUTH_COOKIE: Arc<Mutex<bool>> = Arc::new(Mutex::new(true));
+    static ref TLS_ENABLED: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+    static ref TLS_CERT_PATH: Arc<Mutex<String>> =
+        Arc::new(Mutex::new(String::from("tls/cert.pem")));
+    static ref TLS_KEY_PATH: Arc<Mutex<String>> = Arc::new(Mutex::new(String::from("tls/key.pem")));
+    static ref TLS_SELF_SIGNED: Arc<Mutex<bool>> = Arc::new(Mutex::new(true));
+    static ref HTTP_REDIRECT_ADDR: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    static ref SESSION_LIFETIME_SECS: Arc<Mutex<u64>> = Arc::new(Mutex::new(60 * 60 * 24));
+    static ref SESSION_REFRESH_SECS: Arc<Mutex<u64>> = Arc::new(Mutex::new(60 * 15));
+    static ref LOGO_MAX_SIZE: Arc<Mutex<u32>> = Arc::new(Mutex::new(512));
+    static ref MAX_UPLOAD_MB: Arc<Mutex<u64>> = Arc::new(Mutex::new(10));
+    static ref LOGIN_MAX_FAILURES: Arc<Mutex<u32>> = Arc::new(Mutex::new(5));
+    static ref LOGIN_LOCKOUT_SECS: Arc<Mutex<u64>> = Arc::new(Mutex::new(900));
+    static ref LOGIN_ATTEMPTS_BY_IP: Arc<Mutex<HashMap<IpAddr, LoginAttempts>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref LOGIN_ATTEMPTS_BY_USER: Arc<Mutex<HashMap<String, LoginAttempts>>> =
+        Arc::new(Mutex::new(HashMap::new()));
 }
 
 #[tokio::main]
@@ -93,17 +188,36 @@ async fn main() {
     std::fs::create_dir_all("./sponsors").unwrap();
     std::fs::create_dir_all("./teams").unwrap();
     std::fs::create_dir_all("./login").unwrap();
+    std::fs::create_dir_all("./games").unwrap();
+    std::fs::create_dir_all("./lower_thirds").unwrap();
+    std::fs::create_dir_all(format!("./{}", TEMPLATES_DIR)).unwrap();
+
+    let log_filter = init_logging();
 
-    *SPONSOR_IMG_TAGS.lock().await = tokio::spawn(load_sponsors()).await.unwrap();
+    // Opens the config (or creates it if it doesnt exist) file and load configurations
+    tokio::spawn(read_or_create_config(log_filter))
+        .await
+        .unwrap();
 
+    tokio::spawn(load_or_create_secret()).await.unwrap();
+    tokio::spawn(clean_team_uploads()).await.unwrap();
+    // Matches are loaded first, migrated presets are added to them
+    load_matches().await;
+    tokio::spawn(migrate_team_presets()).await.unwrap();
+    load_countdowns().await;
+    load_popups().await;
+    load_scenes().await;
+    load_theme().await;
+    load_render_fonts().await;
+
+    // Sponsors are loaded after the config since they are downscaled to logo_max_size
+    reload_sponsors().await;
+    load_lower_thirds().await;
+    load_sponsor_impressions().await;
+    tokio::spawn(sponsor_watcher());
     tokio::spawn(sponsor_roll_ticker());
-    tokio::spawn(secret_file_verifier()).await.unwrap();
 
-    *SECRET.lock().await = tokio::fs::read_to_string("login/secrets.txt")
-        .await
-        .unwrap()
-        .trim()
-        .to_string();
+    let max_upload_bytes = *MAX_UPLOAD_MB.lock().await as usize * 1024 * 1024;
 
     // region: --- Routing
 
@@ -113,117 +227,149 @@ async fn main() {
         .route("/overlay", get(chroma_handler)) // Handles get requests for the overlay page
         .route("/teaminfo", get(upload_page_handler)) // Handles get requests for the upload page
         .route("/countdown", get(countdown_handler))
+        .route("/games", get(games_page_handler))
+        .route("/sponsors", get(sponsors_page_handler))
+        .route("/lower_thirds", get(lower_thirds_page_handler))
+        .route("/games/:id", get(game_report_page_handler))
         .route("/login/create", get(create_login_page_handler))
         .route("/login/create", post(create_login_handler))
         .route("/login/", get(login_page_handler))
         .route("/login", get(login_page_handler))
         .route("/login", post(login_handler))
         .route("/style.css", get(css_handler)) // Handles get requests for the css of the app
+        .route("/theme.css", get(theme_css_handler))
         .route("/htmx.min.js", get(htmx_handler)) // Handles get requests for the htmx library
         .route("/app.js", get(app_js_handler))
         .route("/favicon_png", get(favicon_handler))
-        // Routes to update the home team's info
-        .route("/home_add/:points", post(home_add_handler))
+        // Route for logos and sponsor images, addressed by the hash of their contents
+        .route("/img/:hash", get(cached_image_handler))
+        // Routes to display the home team's info
         .route("/hp", put(hp_handler))
         .route("/home_png", get(home_img_handler))
-        // Routes to update the away team's info
-        .route("/away_add/:points", post(away_add_handler))
+        // Routes to display the away team's info
         .route("/ap", put(ap_handler))
         .route("/away_png", get(away_img_handler))
-        // Routes to update the clock
-        .route("/quick_time/:mins/:secs", post(quick_time_handler))
-        .route("/tstart", post(tstart_handler))
-        .route("/tstop", post(tstop_handler))
+        // Routes to display the clock
         .route("/time", put(time_handler))
         .route("/time_dashboard", put(dashboard_time_display_handler))
-        .route("/mins_up", post(mins_up_handler))
-        .route("/mins_down", post(mins_down_handler))
-        .route("/secs_up", post(secs_up_handler))
-        .route("/secs_down", post(secs_down_handler))
-        // Route to update the team name with a POST form
-        .route("/", post(tname_handler))
         // Routes to display the team names
         .route("/hdisp", put(hdisp_handler))
         .route("/adisp", put(adisp_handler))
         // Routes for the scoreboard's info and configuration
         .route("/chromargb", put(chromargb_handler))
+        .route(
+            "/overlay_background_form",
+            put(overlay_background_form_handler),
+        )
         .route("/score", put(score_handler))
         .route("/time_and_quarter", put(time_and_quarter_handler))
+        // Routes for the scorebug rendered on the server
+        .route("/render/scorebug.svg", get(scorebug_svg_handler))
+        .route("/render/scorebug.png", get(scorebug_png_handler))
         .route("/hname_score", put(hname_scoreboard_handler))
+        .route("/hlogo_score", put(home_logo_scoreboard_handler))
+        .route("/alogo_score", put(away_logo_scoreboard_handler))
         .route("/aname_score", put(aname_scoreboard_handler))
+        .route("/team_colors_css", put(team_colors_css_handler))
         .route("/quarter", put(quarter_handler))
-        .route("/show_quarter", post(quarter_show_handler))
-        // Routes to change quarter info
-        .route("/change_quarter/:q", post(quarter_change_handler))
         .route("/show_quarter_css", put(show_quarter_css_handler))
         // Routes for team management
-        .route("/add_team", post(add_team_handler))
-        .route("/load_team/:id", post(load_team_handler))
         .route("/team_selectors", put(team_selectors_handler))
-        .route("/delete_preset/:id", post(delete_preset_handler))
+        // Routes for the team API
+        .route("/api/teams", get(list_teams_handler))
+        .route("/api/teams/:id", get(get_team_handler))
+        .route("/api/teams/:id/logo", get(team_logo_handler))
+        .route("/api/teams/:id/roster", get(get_roster_handler))
+        .route(
+            "/api/teams/:id/roster/:number/headshot",
+            get(headshot_handler),
+        )
+        .route("/roster_options/:side", put(roster_options_handler))
+        // Routes for saved matches, the preset API reads them the way presets were read before the team library
+        .route("/match_list", put(match_list_handler))
+        .route("/api/presets", get(list_presets_handler))
+        .route("/api/presets/:id", get(get_preset_handler))
+        .route("/api/presets/:id/logo/:side", get(preset_logo_handler))
+        // Routes for the box score
+        .route("/api/box_score", get(box_score_handler))
+        .route("/api/events", get(game_events_handler))
+        .route("/stats_panel", put(stats_panel_handler))
+        // Routes for the game history
+        .route("/api/games", get(list_games_handler))
+        .route("/game_list", put(game_list_handler))
         // Routes for the sponsor roll
         .route("/sponsor_roll", put(sponsor_roll_handler))
-        .route("/show_sponsor_roll", post(show_sponsor_roll_handler))
         .route("/sponsor_roll_css", put(sponsor_roll_css_handler))
+        // Routes for sponsor management
+        .route("/api/sponsors", get(list_sponsors_handler))
+        .route("/sponsor_list", put(sponsor_list_handler))
         // Routes for the countdown
-        .route("/show_countdown", post(show_countdown_handler))
         .route("/countdown_css", put(countdown_css_handler))
         .route("/countdown_display", put(countdown_display_handler))
-        .route(
-            "/quick_countdown/:mins/:secs",
-            post(quick_countdown_handler),
-        )
-        .route("/countdown_mins_up", post(countdown_mins_up_handler))
-        .route("/countdown_mins_down", post(countdown_mins_down_handler))
-        .route("/countdown_secs_up", post(countdown_secs_up_handler))
-        .route("/countdown_secs_down", post(countdown_secs_down_handler))
-        .route("/start_countdown", post(start_countdown_handler))
-        .route("/stop_countdown", post(stop_countdown_handler))
-        .route("/update_countdown_title", post(countdown_title_handler))
+        .route("/countdown_list", put(countdown_list_handler))
+        .route("/api/countdowns", get(list_countdowns_handler))
         .route(
             "/countdown_dashboard",
             put(dashboard_countdown_display_handler),
         )
         // Routes for misc. buttons
-        .route("/popup/:type", post(popup_handler))
         .route("/popup", put(popup_show_handler))
         .route("/popup_css", put(popup_css_handler))
-        // Routes to reset the scoreboard
-        .route("/reset_scoreboard", post(reset_scoreboard_handler))
+        .route("/popup_buttons", put(popup_buttons_handler))
+        .route("/api/popups", get(list_popups_handler))
+        .route("/api/popups/queue", get(popup_queue_handler))
+        // Routes for themes
+        .route("/theme/:name", put(theme_template_handler))
+        .route("/api/theme", get(theme_info_handler))
+        // Routes for scenes
+        .route("/scene_css", put(scene_css_handler))
+        .route("/scene_buttons", put(scene_buttons_handler))
+        .route("/scene_options", put(scene_options_handler))
+        .route("/api/scenes", get(list_scenes_handler))
+        // Routes for lower thirds
+        .route("/lower_third", put(lower_third_handler))
+        .route("/lower_third_css", put(lower_third_css_handler))
+        .route("/lower_third_list", put(lower_third_list_handler))
+        .route("/api/lower_thirds", get(list_lower_thirds_handler))
+        .route("/api/lower_thirds/on_air", get(on_air_lower_third_handler))
         // Routes for the favicon
         .route("/favicon.ico", get(favicon_handler))
         // Routes head requests for calculating latency
         .route("/ping", head(|| async { StatusCode::OK }))
+        // Routes that change state, these need a session and a matching CSRF token
+        .merge(mutating_routes(max_upload_bytes))
+        // Routes that read private data, these need a session
+        .merge(private_routes())
         // Route the 404 page
         .fallback_service(get(|| async {
-            println!(" -> 404: not found");
+            debug!("404: not found");
             (StatusCode::NOT_FOUND, Html("<h1>404 - Not Found</h1>"))
-        }));
+        }))
+        // Keeps active sessions alive by re-issuing their auth token
+        .layer(middleware::from_fn(session_refresh_middleware));
 
     // endregion: --- Routing
 
     // Starts the clock tickers
     tokio::spawn(clock_ticker());
     tokio::spawn(countdown_ticker());
-    // Opens the config (or creates it if it doesnt exist) file and load configurations
-    tokio::spawn(read_or_create_config()).await.unwrap();
+    tokio::spawn(popup_ticker());
+    tokio::spawn(lower_third_ticker());
 
     // Gets address from the ADDR mutex
-    let listen_addr = ADDR.lock().await;
-    let listen_addr: String = listen_addr.clone();
+    let listen_addr: String = ADDR.lock().await.clone();
+    let tls_enabled = *TLS_ENABLED.lock().await;
 
-    // Bind the server to the address
-    println!(
-        "Listening on: {}\nType \"stop\" to do shut down the server gracefully\n",
-        listen_addr
-    );
-    let listener = tokio::net::TcpListener::bind(listen_addr).await.unwrap(); // Binds the listener to the address
+    if *SECURE_AUTH_COOKIE.lock().await && !tls_enabled {
+        warn!("secure_auth_cookie is on but tls is off, logging in will only work behind an HTTPS reverse proxy");
+    }
 
     // Creates a oneshot channel to be able to shut down the server gracefully
     let (tx, rx) = tokio::sync::oneshot::channel();
 
     // Spawns a task to listen for the "stop" command which shuts down the server
-    tokio::spawn(async move {
+    // Reading stdin blocks, so it runs on the blocking pool instead of a runtime worker
+    tokio::task::spawn_blocking(move || {
         let stdin = io::stdin();
         for line in stdin.lock().lines() {
             if line.unwrap() == "stop" {
@@ -233,31 +379,385 @@ async fn main() {
         }
     });
 
-    // Start the server
-    let server = axum::serve(listener, app).with_graceful_shutdown(async {
-        let _ = rx.await;
-        println!(" -> SERVER: shutting down");
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+
+    if tls_enabled {
+        let tls_config = load_tls_config().await;
+        let listen_addr: SocketAddr = listen_addr
+            .parse()
+            .expect("listen_addr must be an ip:port pair when tls is enabled");
+
+        // Optionally redirects plain HTTP to the HTTPS listener
+        if let Some(redirect_addr) = HTTP_REDIRECT_ADDR.lock().await.clone() {
+            tokio::spawn(http_redirect_server(redirect_addr, listen_addr.port()));
+        }
+
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            let _ = rx.await;
+            info!("SERVER: shutting down");
+            shutdown_handle.graceful_shutdown(Some(Duration::from_secs(10)));
+        });
+
+        // Bind the server to the address
+        info!("Listening on: https://{}", listen_addr);
+        info!("Type \"stop\" to do shut down the server gracefully");
+
+        // Prints an error if an error occurs whie starting the server
+        if let Err(err) = axum_server::bind_rustls(listen_addr, tls_config)
+            .handle(handle)
+            .serve(app)
+            .await
+        {
+            error!("{}", err);
+        }
+    } else {
+        // Bind the server to the address
+        info!("Listening on: {}", listen_addr);
+        info!("Type \"stop\" to do shut down the server gracefully");
+        let listener = tokio::net::TcpListener::bind(listen_addr).await.unwrap(); // Binds the listener to the address
+
+        // Start the server
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = rx.await;
+            info!("SERVER: shutting down");
+        });
+
+        // Prints an error if an error occurs whie starting the server
+        if let Err(err) = server.await {
+            error!("{}", err);
+        }
+    }
+    info!("SERVER: gracefully shut down");
+}
+
+// Routes that change state, every one of them is checked by csrf_middleware
+fn mutating_routes(max_upload_bytes: usize) -> Router {
+    Router::new()
+        // Route to log out every session
+        .route("/rotate_secret", post(rotate_secret_handler))
+        // Routes to update the home team's info
+        .route("/home_add/:points", post(home_add_handler))
+        // Routes to update the away team's info
+        .route("/away_add/:points", post(away_add_handler))
+        // Route to record a typed score for either team
+        .route("/score/:side", post(score_kind_handler))
+        // Route to archive the current game
+        .route("/finalize_game", post(finalize_game_handler))
+        // Routes to update the clock
+        .route("/quick_time/:mins/:secs", post(quick_time_handler))
+        .route("/tstart", post(tstart_handler))
+        .route("/tstop", post(tstop_handler))
+        .route("/mins_up", post(mins_up_handler))
+        .route("/mins_down", post(mins_down_handler))
+        .route("/secs_up", post(secs_up_handler))
+        .route("/secs_down", post(secs_down_handler))
+        // Route to update the team name with a POST form
+        .route("/", post(tname_handler))
+        // Routes to change quarter info
+        .route("/show_quarter", post(quarter_show_handler))
+        .route("/change_quarter/:q", post(quarter_change_handler))
+        // Routes for team management
+        .route("/add_team", post(add_team_handler))
+        .route("/load_team/:side/:id", post(load_team_handler))
+        .route("/delete_team/:id", post(delete_team_handler))
+        // Routes for the team API
+        .route(
+            "/api/teams/:id",
+            put(put_team_handler).patch(patch_team_handler),
+        )
+        .route("/api/teams/:id/logo", put(replace_team_logo_handler))
+        .route("/api/teams/import", post(import_teams_handler))
+        .route("/api/presets/import", post(import_presets_handler))
+        // Routes for saved matches
+        .route("/matches", post(save_match_handler))
+        .route("/load_match/:id", post(load_match_handler))
+        .route("/delete_match/:id", post(delete_match_handler))
+        .route("/api/presets", post(create_preset_handler))
+        .route(
+            "/api/presets/:id",
+            put(put_preset_handler)
+                .patch(patch_preset_handler)
+                .delete(delete_preset_handler),
+        )
+        .route(
+            "/api/presets/:id/logo/:side",
+            put(replace_preset_logo_handler),
+        )
+        // Routes for the roster API
+        .route("/api/teams/:id/roster", put(put_roster_handler))
+        .route("/api/teams/:id/roster/import", post(import_roster_handler))
+        .route(
+            "/api/teams/:id/roster/:number",
+            put(put_player_handler).delete(delete_player_handler),
+        )
+        .route(
+            "/api/teams/:id/roster/:number/headshot",
+            put(replace_headshot_handler),
+        )
+        // Routes for the sponsor roll
+        .route("/show_sponsor_roll", post(show_sponsor_roll_handler))
+        // Routes for sponsor management
+        .route("/sponsors/upload", post(upload_sponsor_handler))
+        .route("/sponsors/reload", post(reload_sponsors_handler))
+        .route("/sponsors/:file", delete(delete_sponsor_handler))
+        .route("/sponsors/:file/toggle", post(toggle_sponsor_handler))
+        .route("/sponsors/:file/settings", post(sponsor_settings_handler))
+        .route(
+            "/sponsors/impressions/reset",
+            post(reset_sponsor_impressions_handler),
+        )
+        .route(
+            "/sponsors/:file/move/:direction",
+            post(move_sponsor_handler),
+        )
+        // Routes for the countdown
+        .route("/show_countdown", post(show_countdown_handler))
+        .route(
+            "/quick_countdown/:mins/:secs",
+            post(quick_countdown_handler),
+        )
+        .route("/countdown_mins_up", post(countdown_mins_up_handler))
+        .route("/countdown_mins_down", post(countdown_mins_down_handler))
+        .route("/countdown_secs_up", post(countdown_secs_up_handler))
+        .route("/countdown_secs_down", post(countdown_secs_down_handler))
+        .route("/start_countdown", post(start_countdown_handler))
+        .route("/stop_countdown", post(stop_countdown_handler))
+        .route("/update_countdown_title", post(countdown_title_handler))
+        .route("/countdowns", post(add_countdown_handler))
+        .route("/countdowns/:id/select", post(select_countdown_handler))
+        .route("/countdowns/:id", delete(delete_countdown_handler))
+        // Routes for misc. buttons
+        .route("/popup/:name", post(popup_handler))
+        .route("/popup_clear", post(clear_popups_handler))
+        // Routes for the popup API
+        .route("/api/popups/queue", post(queue_custom_popup_handler))
+        .route(
+            "/api/popups/:name",
+            put(put_popup_handler).delete(delete_popup_handler),
+        )
+        // Routes for lower thirds
+        .route("/lower_thirds", post(add_lower_third_handler))
+        .route("/lower_thirds/clear", post(clear_lower_third_handler))
+        .route("/lower_thirds/:id", delete(delete_lower_third_handler))
+        .route("/lower_thirds/:id/take", post(take_lower_third_handler))
+        .route(
+            "/api/lower_thirds/take",
+            post(take_custom_lower_third_handler),
+        )
+        // Routes for scenes
+        .route("/scenes/:id/take", post(take_scene_handler))
+        .route(
+            "/api/scenes/:id",
+            put(put_scene_handler).delete(delete_scene_handler),
+        )
+        // Routes for the overlay background
+        .route("/overlay_background", post(overlay_background_handler))
+        // Routes to reset the scoreboard
+        .route("/reset_scoreboard", post(reset_scoreboard_handler))
+        // Caps the size of request bodies, mostly logo uploads
+        .layer(DefaultBodyLimit::max(max_upload_bytes))
+        .route_layer(middleware::from_fn(csrf_middleware))
+}
+
+fn private_routes() -> Router {
+    Router::new()
+        // Routes to download the team library
+        .route("/api/teams/export", get(export_teams_handler))
+        .route("/api/presets/export", get(export_presets_handler))
+        // Routes to download game reports
+        .route("/api/games/:id", get(get_game_handler))
+        .route("/api/games/:id/csv", get(game_csv_handler))
+        // Route to download the sponsor impressions for billing
+        .route(
+            "/api/sponsors/impressions.csv",
+            get(sponsor_impressions_csv_handler),
+        )
+        .route_layer(middleware::from_fn(session_middleware))
+}
+
+// region: --- Logging
+
+// Sets up console logging and the security log file
+// Returns a handle so the console log level can be changed once the config is loaded
+fn init_logging() -> reload::Handle<EnvFilter, Registry> {
+    let (console_filter, console_filter_handle) = reload::Layer::new(
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_LEVEL)),
+    );
+
+    let security_log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(SECURITY_LOG_FILE)
+        .unwrap();
+
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(console_filter))
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(std::sync::Mutex::new(security_log))
+                .with_filter(Targets::new().with_target(SECURITY_LOG_TARGET, Level::TRACE)),
+        )
+        .init();
+
+    console_filter_handle
+}
+
+// Wraps values that must never end up in the logs, Debug and Display only print a placeholder
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    // Gives access to the wrapped value, only use this where the secret is actually needed
+    fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T> std::fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+// endregion: --- Logging
+
+// region: --- TLS
+
+// Loads the certificate and key used for HTTPS, generating a self-signed pair first if allowed
+async fn load_tls_config() -> RustlsConfig {
+    // Froggi only ships the ring backend, so it has to be picked explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let cert_path = TLS_CERT_PATH.lock().await.clone();
+    let key_path = TLS_KEY_PATH.lock().await.clone();
+
+    if !Path::new(&cert_path).exists() || !Path::new(&key_path).exists() {
+        if *TLS_SELF_SIGNED.lock().await {
+            generate_self_signed_cert(&cert_path, &key_path).await;
+        } else {
+            error!(
+                "TLS: {} or {} doesn't exist, provide them or set tls_self_signed=true",
+                cert_path, key_path
+            );
+            std::process::exit(1);
+        }
+    }
+
+    match RustlsConfig::from_pem_file(&cert_path, &key_path).await {
+        Ok(config) => config,
+        Err(err) => {
+            error!(
+                "TLS: could not load {} and {}: {}",
+                cert_path, key_path, err
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+// Creates a self-signed certificate for localhost and the address Froggi listens on
+async fn generate_self_signed_cert(cert_path: &str, key_path: &str) {
+    let mut names = vec![String::from("localhost"), String::from("127.0.0.1")];
+    if let Ok(addr) = ADDR.lock().await.parse::<SocketAddr>() {
+        if !addr.ip().is_unspecified() {
+            names.push(addr.ip().to_string());
+        }
+    }
+
+    let certified_key = rcgen::generate_simple_self_signed(names.clone())
+        .expect("Failed to generate a self-signed certificate");
+
+    for path in [cert_path, key_path] {
+        if let Some(parent) = Path::new(path).parent() {
+            tokio::fs::create_dir_all(parent).await.unwrap();
+        }
+    }
+    tokio::fs::write(cert_path, certified_key.cert.pem())
+        .await
+        .unwrap();
+    write_private_file(key_path, certified_key.key_pair.serialize_pem().as_bytes())
+        .await
+        .unwrap();
+
+    info!("CREATE: self-signed certificate for {:?}", names);
+    warn!(
+        "TLS: browsers will warn about the self-signed certificate until it is trusted or replaced"
+    );
+}
+
+// Writes a file only the user running froggi can read, for private keys
+async fn write_private_file(path: &str, contents: &[u8]) -> io::Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path).await?;
+    file.write_all(contents).await?;
+    file.flush().await
+}
+
+// Answers plain HTTP requests with a redirect to the same page on the HTTPS listener
+async fn http_redirect_server(redirect_addr: String, https_port: u16) {
+    let redirect = Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        let Some(host) = headers
+            .get(HOST)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse::<Authority>().ok())
+        else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+
+        let port = if https_port == 443 {
+            String::new()
+        } else {
+            format!(":{}", https_port)
+        };
+        let path = uri.path_and_query().map(|x| x.as_str()).unwrap_or("/");
+
+        Redirect::permanent(&format!("https://{}{}{}", host.host(), port, path)).into_response()
     });
 
-    // Prints an error if an error occurs whie starting the server
-    if let Err(err) = server.await {
-        eprintln!(" -> ERROR: {}", err);
+    let listener = match tokio::net::TcpListener::bind(&redirect_addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(
+                "TLS: could not bind the HTTP redirect to {}: {}",
+                redirect_addr, err
+            );
+            return;
+        }
+    };
+
+    info!("Redirecting HTTP on {} to HTTPS", redirect_addr);
+    if let Err(err) = axum::serve(listener, redirect).await {
+        error!("{}", err);
     }
-    println!(" -> SERVER: gracefully shut down");
 }
 
+// endregion: --- TLS
 // region: --- Config fn's
 
 // Function that creates and loads configurations from the config file
-async fn read_or_create_config() {
+async fn read_or_create_config(log_filter: reload::Handle<EnvFilter, Registry>) {
     // Opens or creates the config file if it doesnt exist
     let config = match tokio::fs::read_to_string(CONFIG_FILE).await {
         Ok(cfg) => cfg,
         Err(_) => {
-            println!(" -> CREATE: config file");
+            info!("CREATE: config file");
             tokio::fs::write(
                 CONFIG_FILE,
-                "# FROGGI config file\nchromakey=0, 177, 64\nlisten_addr=0.0.0.0:8080\nsecure_auth_cookie=true",
+                "# FROGGI config file\nchromakey=0, 177, 64\ntransparent_overlay=false\nlisten_addr=0.0.0.0:8080\nsecure_auth_cookie=true\nlogin_max_failures=5\nlogin_lockout_secs=900\nlog_level=info\nsession_lifetime_secs=86400\nsession_refresh_secs=900\ntls=false\ntls_cert_path=tls/cert.pem\ntls_key_path=tls/key.pem\ntls_self_signed=true\n# http_redirect_addr=0.0.0.0:80\nlogo_max_size=512\nmax_upload_mb=10",
             )
             .await
             .unwrap();
@@ -268,14 +768,16 @@ async fn read_or_create_config() {
     // Split up the config file into lines and filter out comments
     let lines: Vec<String> = config
         .split('\n')
-        .filter(|x| !x.starts_with("#"))
+        .filter(|x| !x.starts_with("#") && !x.trim().is_empty())
         .map(|x| x.to_string())
         .collect();
-    println!(" -> CONFIG: {:?}", lines);
 
     // Loops through the lines and sets the configurations
     for i in lines {
         let parts: Vec<&str> = i.split('=').collect();
+        if parts.len() > 1 {
+            debug!("CONFIG: {} = {}", parts[0], parts[1].trim());
+        }
         match parts[0] {
             "chromakey" => {
                 let rgb: Vec<&str> = parts[1].split(',').collect();
@@ -285,20 +787,123 @@ async fn read_or_create_config() {
                 let mut chromakey = CHROMAKEY.lock().await;
                 *chromakey = (r, g, b);
             }
+            "transparent_overlay" => {
+                *TRANSPARENT_OVERLAY.lock().await = parts[1].trim() == "true";
+            }
+            "render_width" => {
+                *RENDER_WIDTH.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "render_height" => {
+                *RENDER_HEIGHT.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "render_font" => {
+                let font = parts[1].trim();
+                *RENDER_FONT.lock().await = (!font.is_empty()).then(|| font.to_string());
+            }
             "listen_addr" => {
                 let mut addr = ADDR.lock().await;
                 *addr = parts[1].trim().to_string();
             }
             "secure_auth_cookie" => {
-                if parts[1].trim() == "false" {
-                    *SECURE_AUTH_COOKIE.lock().await = false;
+                *SECURE_AUTH_COOKIE.lock().await = parts[1].trim() != "false";
+            }
+            "tls" => {
+                *TLS_ENABLED.lock().await = parts[1].trim() == "true";
+            }
+            "tls_cert_path" => {
+                *TLS_CERT_PATH.lock().await = parts[1].trim().to_string();
+            }
+            "tls_key_path" => {
+                *TLS_KEY_PATH.lock().await = parts[1].trim().to_string();
+            }
+            "tls_self_signed" => {
+                *TLS_SELF_SIGNED.lock().await = parts[1].trim() != "false";
+            }
+            "http_redirect_addr" => {
+                let redirect_addr = parts[1].trim();
+                *HTTP_REDIRECT_ADDR.lock().await =
+                    (!redirect_addr.is_empty()).then(|| redirect_addr.to_string());
+            }
+            "login_max_failures" => {
+                *LOGIN_MAX_FAILURES.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "login_lockout_secs" => {
+                *LOGIN_LOCKOUT_SECS.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "session_lifetime_secs" => {
+                *SESSION_LIFETIME_SECS.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "session_refresh_secs" => {
+                *SESSION_REFRESH_SECS.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "logo_max_size" => {
+                *LOGO_MAX_SIZE.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "popup" => {
+                // Popup texts can contain '=' too
+                let value = parts[1..].join("=");
+                match parse_popup_config(&value) {
+                    Some(popup) => {
+                        let mut popups = CONFIG_POPUPS.lock().await;
+                        match popups.iter_mut().find(|p| p.name == popup.name) {
+                            Some(existing) => *existing = popup,
+                            None => popups.push(popup),
+                        }
+                    }
+                    None => warn!("CONFIG: invalid popup: {}", value.trim()),
+                }
+            }
+            "theme" => {
+                let theme = parts[1].trim();
+                if valid_theme_name(theme) {
+                    *THEME.lock().await = theme.to_string();
                 } else {
-                    *SECURE_AUTH_COOKIE.lock().await = true;
+                    warn!("CONFIG: invalid theme: {}", theme);
+                }
+            }
+            "max_upload_mb" => {
+                *MAX_UPLOAD_MB.lock().await = parts[1].trim().parse().unwrap();
+            }
+            "log_level" => {
+                // RUST_LOG takes precedence over the config file
+                if std::env::var("RUST_LOG").is_err() {
+                    match EnvFilter::try_new(parts[1].trim()) {
+                        Ok(filter) => log_filter.reload(filter).unwrap(),
+                        Err(err) => warn!("CONFIG: invalid log_level: {}", err),
+                    }
                 }
             }
-            _ => println!(" -> CONFIG: unknown config: {}", parts[0]),
+            _ => warn!("CONFIG: unknown config: {}", parts[0]),
+        }
+    }
+}
+
+// Changes one setting in the config file, the rest of the file and its comments stay as they are
+async fn set_config_value(key: &str, value: &str) -> Result<(), StatusCode> {
+    let config = tokio::fs::read_to_string(CONFIG_FILE)
+        .await
+        .unwrap_or_default();
+    let entry = format!("{}={}", key, value);
+
+    let mut lines: Vec<String> = config.split('\n').map(|line| line.to_string()).collect();
+    match lines
+        .iter()
+        .position(|line| line.split('=').next() == Some(key))
+    {
+        Some(index) => lines[index] = entry,
+        // Keeps the newline at the end of the file if there is one
+        None if lines.last().is_some_and(|line| line.is_empty()) => {
+            lines.insert(lines.len() - 1, entry)
         }
+        None => lines.push(entry),
     }
+
+    tokio::fs::write(CONFIG_FILE, lines.join("\n"))
+        .await
+        .map_err(|err| {
+            warn!("FAIL: could not save {} to {}: {}", key, CONFIG_FILE, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
 }
 
 // endregion: --- Config fn's
@@ -306,140 +911,164 @@ async fn read_or_create_config() {
 
 // Serves the index.html file
 async fn idx_handler(cookies: CookieJar) -> impl IntoResponse {
-    if let Some(auth_cookie) = cookies.get("authToken") {
-        let validation = Validation::default();
-        match decode::<AuthClaims>(
-            &auth_cookie.value(),
-            &DecodingKey::from_secret(SECRET.lock().await.as_bytes()),
-            &validation,
-        ) {
-            Ok(_) => match tokio::fs::File::open("login/logins.txt").await {
-                Ok(_) => {
-                    println!(" -> SERVE: index.html");
-                    return Html(include_str!("html/index.html")).into_response();
-                }
-                Err(_) => {
-                    println!(" -> REDIRECT: login not created yet");
-                    return Redirect::to("/login/create").into_response();
-                }
-            },
-            Err(_) => {
-                println!(" -> REDIRECT: Invalid auth cookie");
-                return Redirect::to("/login").into_response();
-            }
-        }
-    } else {
-        println!(" -> REDIRECT: No auth cookie");
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
         return Redirect::to("/login").into_response();
     }
+
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("SERVE: index.html");
+            Html(include_str!("html/index.html")).into_response()
+        }
+        Err(_) => {
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
+        }
+    }
 }
 
 // Serves the overlay.html file
 async fn chroma_handler() -> impl IntoResponse {
     match tokio::fs::File::open("login/logins.txt").await {
         Ok(_) => {
-            println!(" -> SERVE: overlay.html");
-            return Html(include_str!("html/scoreboard/overlay.html")).into_response();
+            debug!("SERVE: overlay.html");
+            let overlay = render_template(String::from("overlay.html")).await;
+            // A broken theme.css would otherwise only show up as missing styles
+            let css = render_template(String::from("theme.css")).await;
+
+            match overlay.and_then(|overlay| css.map(|_| overlay)) {
+                Ok(overlay) => Html(overlay.unwrap_or_default()).into_response(),
+                Err(err) => template_error_page(&THEME.lock().await, &err),
+            }
         }
         Err(_) => {
-            println!(" -> REDIRECT: login not created yet");
-            return Redirect::to("/login/create").into_response();
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
         }
     }
 }
 
 // Serve the teaminfo.html file
 async fn upload_page_handler(cookies: CookieJar) -> impl IntoResponse {
-    if let Some(auth_cookie) = cookies.get("authToken") {
-        let validation = Validation::default();
-        match decode::<AuthClaims>(
-            &auth_cookie.value(),
-            &DecodingKey::from_secret(SECRET.lock().await.as_bytes()),
-            &validation,
-        ) {
-            Ok(_) => match tokio::fs::File::open("login/logins.txt").await {
-                Ok(_) => {
-                    println!(" -> SERVE: teaminfo.html");
-                    return Html(include_str!("html/teaminfo/teaminfo.html")).into_response();
-                }
-                Err(_) => {
-                    println!(" -> REDIRECT: login not created yet");
-                    return Redirect::to("/login/create").into_response();
-                }
-            },
-            Err(_) => {
-                println!(" -> REDIRECT: Invalid auth cookie");
-                return Redirect::to("/login").into_response();
-            }
-        }
-    } else {
-        println!(" -> REDIRECT: No auth cookie");
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
         return Redirect::to("/login").into_response();
     }
+
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("SERVE: teaminfo.html");
+            Html(include_str!("html/teaminfo/teaminfo.html")).into_response()
+        }
+        Err(_) => {
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
+        }
+    }
 }
 
 async fn countdown_handler(cookies: CookieJar) -> impl IntoResponse {
-    if let Some(auth_cookie) = cookies.get("authToken") {
-        let validation = Validation::default();
-        match decode::<AuthClaims>(
-            &auth_cookie.value(),
-            &DecodingKey::from_secret(SECRET.lock().await.as_bytes()),
-            &validation,
-        ) {
-            Ok(_) => match tokio::fs::File::open("login/logins.txt").await {
-                Ok(_) => {
-                    println!(" -> SERVE: countdown.html");
-                    return Html(include_str!("html/countdown/countdown.html")).into_response();
-                }
-                Err(_) => {
-                    println!(" -> REDIRECT: login not created yet");
-                    return Redirect::to("/login/create").into_response();
-                }
-            },
-            Err(_) => {
-                println!(" -> REDIRECT: Invalid auth cookie");
-                return Redirect::to("/login").into_response();
-            }
-        }
-    } else {
-        println!(" -> REDIRECT: No auth cookie");
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
         return Redirect::to("/login").into_response();
     }
-}
 
-async fn login_page_handler() -> impl IntoResponse {
     match tokio::fs::File::open("login/logins.txt").await {
         Ok(_) => {
-            println!(" -> SERVE: login.html");
-            return Html(include_str!("html/login/login.html")).into_response();
+            debug!("SERVE: countdown.html");
+            Html(include_str!("html/countdown/countdown.html")).into_response()
         }
         Err(_) => {
-            println!(" -> REDIRECT: login not created yet");
-            return Redirect::to("/login/create").into_response();
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
         }
     }
 }
 
-async fn create_login_page_handler() -> impl IntoResponse {
+async fn games_page_handler(cookies: CookieJar) -> impl IntoResponse {
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
+        return Redirect::to("/login").into_response();
+    }
+
     match tokio::fs::File::open("login/logins.txt").await {
         Ok(_) => {
-            return {
-                println!(" -> REDIRECT: login already created");
-                Redirect::to("/login").into_response()
-            }
+            debug!("SERVE: games.html");
+            Html(include_str!("html/games/games.html")).into_response()
         }
         Err(_) => {
-            return {
-                println!(" -> SERVE: create_login.html");
-                Html(include_str!("html/login/create_login.html")).into_response()
-            }
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
         }
     }
 }
 
-// Serves the main css file
+async fn sponsors_page_handler(cookies: CookieJar) -> impl IntoResponse {
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
+        return Redirect::to("/login").into_response();
+    }
+
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("SERVE: sponsors.html");
+            Html(include_str!("html/sponsors/sponsors.html")).into_response()
+        }
+        Err(_) => {
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
+        }
+    }
+}
+
+async fn lower_thirds_page_handler(cookies: CookieJar) -> impl IntoResponse {
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
+        return Redirect::to("/login").into_response();
+    }
+
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("SERVE: lower_thirds.html");
+            Html(include_str!("html/lower_thirds/lower_thirds.html")).into_response()
+        }
+        Err(_) => {
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
+        }
+    }
+}
+
+async fn login_page_handler() -> impl IntoResponse {
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("SERVE: login.html");
+            Html(include_str!("html/login/login.html")).into_response()
+        }
+        Err(_) => {
+            debug!("REDIRECT: login not created yet");
+            Redirect::to("/login/create").into_response()
+        }
+    }
+}
+
+async fn create_login_page_handler() -> impl IntoResponse {
+    match tokio::fs::File::open("login/logins.txt").await {
+        Ok(_) => {
+            debug!("REDIRECT: login already created");
+            Redirect::to("/login").into_response()
+        }
+        Err(_) => {
+            debug!("SERVE: create_login.html");
+            Html(include_str!("html/login/create_login.html")).into_response()
+        }
+    }
+}
+
+// Serves the main css file
 async fn css_handler() -> impl IntoResponse {
-    println!(" -> SERVE: style.css");
+    debug!("SERVE: style.css");
     let body = include_str!("html/style.css");
     let body = Body::from(body);
     Response::builder()
@@ -450,7 +1079,7 @@ async fn css_handler() -> impl IntoResponse {
 
 // Serves the htmx library
 async fn htmx_handler() -> impl IntoResponse {
-    println!(" -> SERVE: htmx.min.js");
+    debug!("SERVE: htmx.min.js");
     let body = include_str!("html/htmx.min.js");
     let body = Body::from(body);
     Response::builder()
@@ -460,7 +1089,7 @@ async fn htmx_handler() -> impl IntoResponse {
 }
 
 async fn app_js_handler() -> impl IntoResponse {
-    println!(" -> SERVE: app.js");
+    debug!("SERVE: app.js");
     let body = include_str!("app.js");
     let body = Body::from(body);
     Response::builder()
@@ -470,7 +1099,7 @@ async fn app_js_handler() -> impl IntoResponse {
 }
 
 async fn favicon_handler() -> impl IntoResponse {
-    println!(" -> SERVE: favicon.ico");
+    debug!("SERVE: favicon.ico");
     let body = include_bytes!("html/favicon.png");
     let body = Body::from(body.to_vec());
     Response::builder()
@@ -491,7 +1120,7 @@ struct UpdNames {
 
 // Handles the form to update the team names
 async fn tname_handler(Form(names): Form<UpdNames>) {
-    println!(" -> TEAMS: update names: {} - {}", names.home, names.away);
+    info!("TEAMS: update names: {} - {}", names.home, names.away);
     let mut home_name = HOME_NAME.lock().await;
     let mut away_name = AWAY_NAME.lock().await;
     *home_name = names.home;
@@ -523,38 +1152,186 @@ async fn aname_scoreboard_handler() -> Html<String> {
 }
 
 // Handles and returns requests for the home team's logo
-async fn home_img_handler() -> impl IntoResponse {
-    let home_image = HOME_IMG_DATA.lock().await.clone();
-    let body = Body::from(home_image);
-    Response::builder()
-        .header(CONTENT_TYPE, IMAGE_PNG.to_string())
-        .body(body)
-        .unwrap()
+async fn home_img_handler(headers: HeaderMap) -> impl IntoResponse {
+    loaded_logo_response("home", &HOME_IMG_DATA, &headers).await
 }
 
 // Handles and returns requests for the away team's logo
-async fn away_img_handler() -> impl IntoResponse {
-    let away_image = AWAY_IMG_DATA.lock().await.clone();
-    let body = Body::from(away_image);
-    Response::builder()
-        .header(CONTENT_TYPE, IMAGE_PNG.to_string())
-        .body(body)
-        .unwrap()
+async fn away_img_handler(headers: HeaderMap) -> impl IntoResponse {
+    loaded_logo_response("away", &AWAY_IMG_DATA, &headers).await
+}
+
+// The url stays the same when another team is loaded, so browsers have to check the ETag every time
+async fn loaded_logo_response(
+    side: &str,
+    img_data: &Mutex<Vec<u8>>,
+    headers: &HeaderMap,
+) -> Response<Body> {
+    let hash = IMAGE_CACHE.lock().await.hash(side).cloned();
+    match hash {
+        Some(hash) => cached_image_response(&hash, headers, "no-cache").await,
+        None => image_response(img_data.lock().await.clone()),
+    }
+}
+
+// Handles and returns the home team's logo for the scoreboard, linked by the hash of the logo so it is cached
+async fn home_logo_scoreboard_handler() -> Html<String> {
+    logo_img("home", "Home").await
+}
+
+// Handles and returns the away team's logo for the scoreboard
+async fn away_logo_scoreboard_handler() -> Html<String> {
+    logo_img("away", "Away").await
+}
+
+async fn logo_img(side: &str, alt: &str) -> Html<String> {
+    match image_url(side).await {
+        Some(url) => Html(format!(
+            "<img class=\"logo\" src=\"{}\" alt=\"{}\" height=\"28px\" width=\"auto\">",
+            url, alt
+        )),
+        None => Html(String::new()),
+    }
 }
 
 // endregion: --- Team names
-// region: --- Home handlers
+// region: --- Team colors
+
+// The colors of a loaded team, the text color is picked from the primary color
+#[derive(Debug, Clone, Copy)]
+struct TeamColors {
+    primary: (u8, u8, u8),
+    secondary: (u8, u8, u8),
+}
+
+impl Default for TeamColors {
+    fn default() -> TeamColors {
+        TeamColors {
+            primary: (255, 255, 255),
+            secondary: (0, 0, 0),
+        }
+    }
+}
 
-// Handles post request chaning the teams points
-async fn home_add_handler(axum::extract::Path(dval): axum::extract::Path<i32>) {
-    let mut home_points = HOME_POINTS.lock().await;
-    if dval < 0 && *home_points + dval >= 0 {
-        *home_points = *home_points + dval;
-    } else if dval > 0 {
-        *home_points = *home_points + dval;
+impl TeamColors {
+    // Reads the colors of a team from the library, colors that can't be parsed fall back to the defaults
+    fn from_team(info: &TeamInfoContainer) -> TeamColors {
+        let default = TeamColors::default();
+        TeamColors {
+            primary: parse_color(&info.primary_color).unwrap_or(default.primary),
+            secondary: parse_color(&info.secondary_color).unwrap_or(default.secondary),
+        }
+    }
+}
+
+// Parses a color written as #rgb, #rrggbb, rgb(r, g, b) or r, g, b
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 => Some((
+                channel(&hex[0..1])? * 17,
+                channel(&hex[1..2])? * 17,
+                channel(&hex[2..3])? * 17,
+            )),
+            6 => Some((
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            _ => None,
+        };
+    }
+
+    let channels = color
+        .strip_prefix("rgb(")
+        .and_then(|c| c.strip_suffix(')'))
+        .unwrap_or(color);
+    let channels: Vec<u8> = channels
+        .split(',')
+        .map(|c| c.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+// Validates a color and writes it as #rrggbb so color inputs can show it
+fn normalize_color(color: &str) -> Option<String> {
+    parse_color(color).map(hex_color)
+}
+
+fn hex_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Relative luminance as defined by WCAG 2
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+// Picks black or white text, whichever has the higher contrast ratio on the background
+fn contrast_text_color(background: (u8, u8, u8)) -> (u8, u8, u8) {
+    let luminance = relative_luminance(background);
+    let contrast_black = (luminance + 0.05) / 0.05;
+    let contrast_white = 1.05 / (luminance + 0.05);
+
+    if contrast_black >= contrast_white {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
     }
 }
 
+// Handles and returns the team colors as css variables for the overlay
+async fn team_colors_css_handler() -> Html<String> {
+    let home = *HOME_COLORS.lock().await;
+    let away = *AWAY_COLORS.lock().await;
+
+    Html(format!(
+        "<style>:root {{ --home-primary: {}; --home-secondary: {}; --home-text: {}; --away-primary: {}; --away-secondary: {}; --away-text: {}; }}</style>",
+        hex_color(home.primary),
+        hex_color(home.secondary),
+        hex_color(contrast_text_color(home.primary)),
+        hex_color(away.primary),
+        hex_color(away.secondary),
+        hex_color(contrast_text_color(away.primary)),
+    ))
+}
+
+// endregion: --- Team colors
+// region: --- Home handlers
+
+// Handles post request chaning the teams points, optionally crediting a player on the roster
+async fn home_add_handler(
+    axum::extract::Path(dval): axum::extract::Path<i32>,
+    player: Option<Form<PlayerRef>>,
+) -> StatusCode {
+    let player = match picked_player("home", player).await {
+        Ok(player) => player,
+        Err(status) => return status,
+    };
+
+    record_score("home", ScoreKind::Points, dval, player, None).await;
+
+    StatusCode::OK
+}
+
 // Handles and returns the home team's points
 async fn hp_handler() -> Html<String> {
     // Displays home points
@@ -565,14 +1342,19 @@ async fn hp_handler() -> Html<String> {
 // endregion: --- Home handlers
 // region: --- Away handlers
 
-// Handles post request chaning the teams points
-async fn away_add_handler(axum::extract::Path(dval): axum::extract::Path<i32>) {
-    let mut away_points = AWAY_POINTS.lock().await;
-    if dval < 0 && *away_points + dval >= 0 {
-        *away_points = *away_points + dval;
-    } else if dval > 0 {
-        *away_points = *away_points + dval;
-    }
+// Handles post request chaning the teams points, optionally crediting a player on the roster
+async fn away_add_handler(
+    axum::extract::Path(dval): axum::extract::Path<i32>,
+    player: Option<Form<PlayerRef>>,
+) -> StatusCode {
+    let player = match picked_player("away", player).await {
+        Ok(player) => player,
+        Err(status) => return status,
+    };
+
+    record_score("away", ScoreKind::Points, dval, player, None).await;
+
+    StatusCode::OK
 }
 
 // Handles and returns the away team's points
@@ -583,629 +1365,6620 @@ async fn ap_handler() -> Html<String> {
 }
 
 // endregion: --- Away Handlers
-// region: --- Clock handlers
-
-// Sets the clock to a quick time in the path
-async fn quick_time_handler(axum::extract::Path((mins, secs)): axum::extract::Path<(u32, u32)>) {
-    *TIME_MINS.lock().await = mins;
-    *TIME_SECS.lock().await = secs;
+// region: --- Game events
+
+// How points were scored, the short names are accepted too so the dashboard and scripts stay readable
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ScoreKind {
+    // Points added without a type, like the +/- buttons on the dashboard
+    Points,
+    #[serde(alias = "ft")]
+    FreeThrow,
+    #[serde(alias = "2pt")]
+    TwoPointer,
+    #[serde(alias = "3pt")]
+    ThreePointer,
+    #[serde(alias = "td")]
+    Touchdown,
+    #[serde(alias = "fg")]
+    FieldGoal,
+    #[serde(alias = "pat")]
+    ExtraPoint,
+    #[serde(alias = "2pc")]
+    TwoPointConversion,
+    Safety,
+    Goal,
 }
 
-// Handles and returns the time formatted as "mm:ss"
-async fn time_handler() -> Html<String> {
-    let time_mins = TIME_MINS.lock().await;
-    let time_secs = TIME_SECS.lock().await;
-    Html(format!("{}:{:02?}", *time_mins, *time_secs))
-}
+impl ScoreKind {
+    // Points a score of this kind is worth, None if the points have to be given
+    fn points(self) -> Option<i32> {
+        match self {
+            ScoreKind::Points => None,
+            ScoreKind::FreeThrow | ScoreKind::ExtraPoint | ScoreKind::Goal => Some(1),
+            ScoreKind::TwoPointer | ScoreKind::TwoPointConversion | ScoreKind::Safety => Some(2),
+            ScoreKind::ThreePointer | ScoreKind::FieldGoal => Some(3),
+            ScoreKind::Touchdown => Some(6),
+        }
+    }
 
-// Handles and returns the minutes of the time
-async fn dashboard_time_display_handler() -> Html<String> {
-    Html(format!(
-        "{}:{:02?}",
-        *TIME_MINS.lock().await,
-        *TIME_SECS.lock().await
-    ))
+    fn as_str(self) -> &'static str {
+        match self {
+            ScoreKind::Points => "points",
+            ScoreKind::FreeThrow => "free_throw",
+            ScoreKind::TwoPointer => "two_pointer",
+            ScoreKind::ThreePointer => "three_pointer",
+            ScoreKind::Touchdown => "touchdown",
+            ScoreKind::FieldGoal => "field_goal",
+            ScoreKind::ExtraPoint => "extra_point",
+            ScoreKind::TwoPointConversion => "two_point_conversion",
+            ScoreKind::Safety => "safety",
+            ScoreKind::Goal => "goal",
+        }
+    }
 }
 
-// Increases the minutes of the time by 1
-async fn mins_up_handler() {
-    let mut time_mins = TIME_MINS.lock().await;
-    *time_mins += 1;
+// Something that happened in the game, kept in order for the box score and game reports
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct GameEvent {
+    // Unix time the event was recorded
+    at: u64,
+    period: u8,
+    // Game clock when the event was recorded
+    clock: String,
+    side: String,
+    #[serde(flatten)]
+    action: GameAction,
 }
 
-// Decreases the minutes of the time by 1
-async fn mins_down_handler() {
-    let mut time_mins = TIME_MINS.lock().await;
-    if *time_mins > 0 {
-        *time_mins -= 1;
-    }
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GameAction {
+    Score {
+        kind: ScoreKind,
+        points: i32,
+        player: Option<Player>,
+        assist: Option<Player>,
+    },
+    Foul {
+        player: Option<Player>,
+    },
 }
 
-// Increases the seconds of the time by 1
-async fn secs_up_handler() {
-    let mut time_secs = TIME_SECS.lock().await;
-    if *time_secs < 59 {
-        *time_secs += 1;
-    } else {
-        let mut time_mins = TIME_MINS.lock().await;
-        *time_mins = *time_mins + 1;
-        *time_secs = 0;
-    }
+// Form of the typed scoring route, points are only read for the points kind
+#[derive(Deserialize)]
+struct ScoreForm {
+    kind: ScoreKind,
+    points: Option<i32>,
+    player: Option<String>,
+    assist: Option<String>,
 }
 
-// Decreases the seconds of the time by 1
-async fn secs_down_handler() {
-    let mut time_secs = TIME_SECS.lock().await;
-    let mut time_mins = TIME_MINS.lock().await;
-    if *time_secs > 0 {
-        *time_secs -= 1;
-    } else if *time_mins - 1 > 0 {
-        *time_mins = *time_mins - 1;
-        *time_secs = 59;
-    }
+// Box score stats of a team, computed from the game events
+#[derive(Serialize, Deserialize, Default)]
+struct TeamStats {
+    name: String,
+    points: i32,
+    fouls: u32,
+    scores: HashMap<String, u32>,
+    players: Vec<PlayerStats>,
 }
 
-// Ticks the clock down if the clock is not stopped
-async fn clock_ticker() {
-    loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        let mut time_started = TIME_STARTED.lock().await;
-        if *time_started {
-            let mut time_mins = TIME_MINS.lock().await;
-            let mut time_secs = TIME_SECS.lock().await;
-            if *time_secs == 0 {
-                if *time_mins == 0 {
-                    *time_started = false;
-                } else {
-                    *time_mins -= 1;
-                    *time_secs = 59;
-                }
-            } else {
-                *time_secs -= 1;
-            }
-        }
-    }
+#[derive(Serialize, Deserialize)]
+struct PlayerStats {
+    number: String,
+    name: String,
+    points: i32,
+    fouls: u32,
+    assists: u32,
+    scores: HashMap<String, u32>,
 }
 
-// Starts the clock
-async fn tstart_handler() {
-    println!(" -> TIMER: start");
-    let mut time_started = TIME_STARTED.lock().await;
-    *time_started = true;
+#[derive(Serialize, Deserialize)]
+struct BoxScore {
+    home: TeamStats,
+    away: TeamStats,
 }
 
-// Stops the clock
-async fn tstop_handler() {
-    println!(" -> TIMER: stop");
-    let mut time_started = TIME_STARTED.lock().await;
-    *time_started = false;
+async fn push_event(side: &str, action: GameAction) {
+    let event = GameEvent {
+        at: unix_now(),
+        period: *QUARTER.lock().await,
+        clock: format!("{}:{:02}", *TIME_MINS.lock().await, *TIME_SECS.lock().await),
+        side: side.to_string(),
+        action,
+    };
+
+    debug!("EVENT: {:?}", event);
+    GAME_STARTED_AT.lock().await.get_or_insert(event.at);
+    GAME_EVENTS.lock().await.push(event);
 }
 
-// endregion: --- Clock handlers
-// region: --- Quarter handlers
+// Adds points to a team and records who scored them, points are never taken below zero
+async fn record_score(
+    side: &str,
+    kind: ScoreKind,
+    points: i32,
+    player: Option<Player>,
+    assist: Option<Player>,
+) {
+    let team_points = match side {
+        "home" => &*HOME_POINTS,
+        _ => &*AWAY_POINTS,
+    };
 
-// Handles and returns the current quarter formatted for the scoreboard
-async fn quarter_handler() -> Html<&'static str> {
-    let quarter = QUARTER.lock().await;
-    if *SHOW_QUARTER.lock().await {
-        if *quarter == 1 {
-            return Html("1st");
-        } else if *quarter == 2 {
-            return Html("2nd");
-        } else if *quarter == 3 {
-            return Html("3rd");
-        } else if *quarter == 4 {
-            return Html("4th");
-        } else {
-            return Html("OVERTIME");
+    let mut team_points = team_points.lock().await;
+    if points > 0 || *team_points + points >= 0 {
+        *team_points += points;
+        drop(team_points);
+
+        if let Some(player) = &player {
+            info!("SCORE: {} {:+} by {}", side, points, player.label());
         }
-    } else {
-        return Html("");
+        push_event(
+            side,
+            GameAction::Score {
+                kind,
+                points,
+                player,
+                assist,
+            },
+        )
+        .await;
     }
 }
 
-// Handles the show quarter button
-async fn quarter_show_handler() {
-    let mut show_quarter = SHOW_QUARTER.lock().await;
-    if *show_quarter {
-        *show_quarter = false;
-    } else {
-        *show_quarter = true;
+// Handles the typed scoring form, a score of the points kind needs its points
+async fn score_kind_handler(
+    axum::extract::Path(side): axum::extract::Path<String>,
+    Form(score): Form<ScoreForm>,
+) -> StatusCode {
+    if side != "home" && side != "away" {
+        return StatusCode::NOT_FOUND;
     }
+    let Some(points) = score.kind.points().or(score.points) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let player = match picked_player(
+        &side,
+        Some(Form(PlayerRef {
+            player: score.player,
+        })),
+    )
+    .await
+    {
+        Ok(player) => player,
+        Err(status) => return status,
+    };
+    let assist = match picked_player(
+        &side,
+        Some(Form(PlayerRef {
+            player: score.assist,
+        })),
+    )
+    .await
+    {
+        Ok(assist) => assist,
+        Err(status) => return status,
+    };
+
+    record_score(&side, score.kind, points, player, assist).await;
+    StatusCode::OK
 }
 
-// Handles and returns the css for the show quarter button
-async fn show_quarter_css_handler() -> Html<&'static str> {
-    let show_quarter = SHOW_QUARTER.lock().await;
-    let quarter = QUARTER.lock().await;
+// Adds up the events of one team into its stats
+fn team_stats(name: String, points: i32, side: &str, events: &[GameEvent]) -> TeamStats {
+    let mut stats = TeamStats {
+        name,
+        points,
+        ..TeamStats::default()
+    };
 
-    if *show_quarter {
-        if *quarter == 1 {
-            return Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter1 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 2 {
-            return Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter2 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 3 {
-            return Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter3 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 4 {
-            return Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter4 { background-color: rgb(227, 45, 32); } </style>");
-        } else {
-            return Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter5 { background-color: rgb(227, 45, 32); } </style>");
-        }
-    } else {
-        if *quarter == 1 {
-            return Html("<style> #show-quarter { background-color: #e9981f; } #quarter1 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 2 {
-            return Html("<style> #show-quarter { background-color: #e9981f; } #quarter2 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 3 {
-            return Html("<style> #show-quarter { background-color: #e9981f; } #quarter3 { background-color: rgb(227, 45, 32); } </style>");
-        } else if *quarter == 4 {
-            return Html("<style> #show-quarter { background-color: #e9981f; } #quarter4 { background-color: rgb(227, 45, 32); } </style>");
-        } else {
-            return Html("<style> #show-quarter { background-color: #e9981f; } #quarter5 { background-color: rgb(227, 45, 32); } </style>");
+    fn player_stats<'a>(players: &'a mut Vec<PlayerStats>, player: &Player) -> &'a mut PlayerStats {
+        let index = match players.iter().position(|p| p.number == player.number) {
+            Some(index) => index,
+            None => {
+                players.push(PlayerStats {
+                    number: player.number.clone(),
+                    name: player.name.clone(),
+                    points: 0,
+                    fouls: 0,
+                    assists: 0,
+                    scores: HashMap::new(),
+                });
+                players.len() - 1
+            }
+        };
+        &mut players[index]
+    }
+
+    for event in events.iter().filter(|e| e.side == side) {
+        match &event.action {
+            GameAction::Score {
+                kind,
+                points,
+                player,
+                assist,
+            } => {
+                if *points > 0 {
+                    *stats.scores.entry(kind.as_str().to_string()).or_default() += 1;
+                }
+                if let Some(player) = player {
+                    let player = player_stats(&mut stats.players, player);
+                    player.points += points;
+                    if *points > 0 {
+                        *player.scores.entry(kind.as_str().to_string()).or_default() += 1;
+                    }
+                }
+                if let Some(assist) = assist {
+                    player_stats(&mut stats.players, assist).assists += 1;
+                }
+            }
+            GameAction::Foul { player } => {
+                stats.fouls += 1;
+                if let Some(player) = player {
+                    player_stats(&mut stats.players, player).fouls += 1;
+                }
+            }
         }
     }
+
+    stats.players.sort_by_key(|p| std::cmp::Reverse(p.points));
+    stats
 }
 
-// Changes the quarter to 1
-async fn quarter_change_handler(axum::extract::Path(q): axum::extract::Path<u8>) {
-    let mut quarter = QUARTER.lock().await;
-    *quarter = q;
+async fn box_score() -> BoxScore {
+    let events = GAME_EVENTS.lock().await.clone();
+
+    BoxScore {
+        home: team_stats(
+            HOME_NAME.lock().await.clone(),
+            *HOME_POINTS.lock().await,
+            "home",
+            &events,
+        ),
+        away: team_stats(
+            AWAY_NAME.lock().await.clone(),
+            *AWAY_POINTS.lock().await,
+            "away",
+            &events,
+        ),
+    }
 }
 
-// endregion: --- Quarter handlers
-// region: --- Team preset handlers
+async fn box_score_handler() -> Json<BoxScore> {
+    Json(box_score().await)
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct TeamInfoContainer {
-    home_name: String,
-    home_color: String,
-    away_name: String,
-    away_color: String,
+async fn game_events_handler() -> Json<Vec<GameEvent>> {
+    Json(GAME_EVENTS.lock().await.clone())
+}
+
+// Handles and returns the stats panel for the overlay, it is shown during timeouts unless the scene says otherwise
+async fn stats_panel_handler() -> Html<String> {
+    let shown = match scene_visibility(Widget::Stats).await {
+        WidgetVisibility::Hidden => false,
+        WidgetVisibility::Auto => timeout_showing().await,
+        WidgetVisibility::Shown => true,
+    };
+    if !shown {
+        return Html(String::new());
+    }
+
+    let box_score = box_score().await;
+    let mut html = String::from("<div class=\"stats-panel\">");
+
+    for team in [&box_score.home, &box_score.away] {
+        html += &format!(
+            "<div class=\"stats-team\"><h3>{} <span>{}</span></h3><p>Fouls: {}</p><ul>",
+            html_escape(&team.name),
+            team.points,
+            team.fouls
+        );
+        for player in team.players.iter().filter(|p| p.points > 0).take(3) {
+            html += &format!(
+                "<li>#{} {} <span>{}</span></li>",
+                player.number,
+                html_escape(&player.name),
+                player.points
+            );
+        }
+        html += "</ul></div>";
+    }
+
+    html += "</div>";
+    Html(html)
+}
+
+// endregion: --- Game events
+
+// region: --- Game reports
+
+// An archived game, written to games/<id>.json when the game is finalized
+#[derive(Serialize, Deserialize)]
+struct GameReport {
+    id: String,
+    started_at: u64,
+    finished_at: u64,
+    duration_secs: u64,
+    home_team_id: Option<String>,
+    away_team_id: Option<String>,
+    periods: Vec<PeriodScore>,
+    box_score: BoxScore,
+    events: Vec<GameEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PeriodScore {
+    period: u8,
+    home: i32,
+    away: i32,
+}
+
+// A game in the history list, without its events
+#[derive(Serialize)]
+struct GameSummary {
+    id: String,
+    finished_at: u64,
+    home_name: String,
+    home_points: i32,
+    away_name: String,
+    away_points: i32,
+}
+
+// Game ids are the unix time the game was finalized
+fn valid_game_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+}
+
+// Formats a unix time in the local time zone of the server
+fn format_unix_time(secs: u64) -> String {
+    match chrono::DateTime::from_timestamp(secs as i64, 0) {
+        Some(time) => time
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        None => secs.to_string(),
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+// Adds up the points of each period from the score events, scoreless periods up to the current one included
+fn period_scores(events: &[GameEvent], current_period: u8) -> Vec<PeriodScore> {
+    let last_period = events
+        .iter()
+        .map(|event| event.period)
+        .chain([current_period])
+        .max()
+        .unwrap_or(1)
+        .max(1);
+
+    let mut periods: Vec<PeriodScore> = (1..=last_period)
+        .map(|period| PeriodScore {
+            period,
+            home: 0,
+            away: 0,
+        })
+        .collect();
+
+    for event in events {
+        let GameAction::Score { points, .. } = &event.action else {
+            continue;
+        };
+        let Some(period) = periods.get_mut(usize::from(event.period.max(1)) - 1) else {
+            continue;
+        };
+
+        match event.side.as_str() {
+            "home" => period.home += points,
+            _ => period.away += points,
+        }
+    }
+
+    periods
+}
+
+// Shows a period number the way the scoreboard does
+fn period_label(period: u8) -> String {
+    match period {
+        // There is no period 0, it is counted as the first one like period_scores does
+        0 | 1 => String::from("1st"),
+        2 => String::from("2nd"),
+        3 => String::from("3rd"),
+        4 => String::from("4th"),
+        5 => String::from("OT"),
+        _ => format!("{}OT", period - 4),
+    }
+}
+
+async fn read_game(id: &str) -> Option<GameReport> {
+    if !valid_game_id(id) {
+        return None;
+    }
+
+    let json = tokio::fs::read_to_string(format!("./games/{}.json", id))
+        .await
+        .ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+// Archives the current game, the scoreboard itself is left alone until it is reset
+async fn finalize_game_handler() -> Result<Json<GameSummary>, StatusCode> {
+    let finished_at = unix_now();
+    let id = finished_at.to_string();
+
+    if Path::new(&format!("./games/{}.json", id)).exists() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let events = GAME_EVENTS.lock().await.clone();
+    let started_at = GAME_STARTED_AT.lock().await.unwrap_or(finished_at);
+
+    let report = GameReport {
+        id: id.clone(),
+        started_at,
+        finished_at,
+        duration_secs: finished_at.saturating_sub(started_at),
+        home_team_id: HOME_TEAM_ID.lock().await.clone(),
+        away_team_id: AWAY_TEAM_ID.lock().await.clone(),
+        periods: period_scores(&events, *QUARTER.lock().await),
+        box_score: box_score().await,
+        events,
+    };
+
+    let json = serde_json::to_string(&report).expect("Failed to serialize game report");
+    if let Err(e) = tokio::fs::write(format!("./games/{}.json", id), json).await {
+        warn!("FAIL: could not write game report {}: {}", id, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    info!(
+        "GAME: finalized {} ({} {} - {} {})",
+        id,
+        report.box_score.home.name,
+        report.box_score.home.points,
+        report.box_score.away.points,
+        report.box_score.away.name
+    );
+
+    Ok(Json(game_summary(&report)))
+}
+
+fn game_summary(report: &GameReport) -> GameSummary {
+    GameSummary {
+        id: report.id.clone(),
+        finished_at: report.finished_at,
+        home_name: report.box_score.home.name.clone(),
+        home_points: report.box_score.home.points,
+        away_name: report.box_score.away.name.clone(),
+        away_points: report.box_score.away.points,
+    }
+}
+
+// Lists every archived game, newest first
+async fn game_summaries() -> Vec<GameSummary> {
+    let mut entries = tokio::fs::read_dir("./games").await.unwrap();
+    let mut games = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(id) = file_name.strip_suffix(".json") {
+            if let Some(report) = read_game(id).await {
+                games.push(game_summary(&report));
+            }
+        }
+    }
+
+    games.sort_by_key(|g| std::cmp::Reverse(g.finished_at));
+    games
+}
+
+async fn list_games_handler() -> Json<Vec<GameSummary>> {
+    Json(game_summaries().await)
+}
+
+async fn get_game_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Response<Body>, StatusCode> {
+    if !valid_game_id(&id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let json = tokio::fs::read(format!("./games/{}.json", id))
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"game-{}.json\"", id),
+        )
+        .body(Body::from(json))
+        .unwrap())
+}
+
+// Handles and returns the event log of a game as csv, with the running score after each event
+async fn game_csv_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Response<Body>, StatusCode> {
+    let report = read_game(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut home_score = 0;
+    let mut away_score = 0;
+
+    writer
+        .write_record([
+            "period",
+            "clock",
+            "team",
+            "event",
+            "kind",
+            "points",
+            "player_number",
+            "player_name",
+            "assist_number",
+            "assist_name",
+            "home_score",
+            "away_score",
+        ])
+        .unwrap();
+
+    for event in &report.events {
+        let team = match event.side.as_str() {
+            "home" => &report.box_score.home.name,
+            _ => &report.box_score.away.name,
+        };
+        let (action, kind, points, player, assist) = match &event.action {
+            GameAction::Score {
+                kind,
+                points,
+                player,
+                assist,
+            } => {
+                match event.side.as_str() {
+                    "home" => home_score += points,
+                    _ => away_score += points,
+                }
+                ("score", kind.as_str(), points.to_string(), player, assist)
+            }
+            GameAction::Foul { player } => ("foul", "", String::new(), player, &None),
+        };
+
+        writer
+            .write_record([
+                period_label(event.period).as_str(),
+                &event.clock,
+                team,
+                action,
+                kind,
+                &points,
+                player.as_ref().map_or("", |p| &p.number),
+                player.as_ref().map_or("", |p| &p.name),
+                assist.as_ref().map_or("", |p| &p.number),
+                assist.as_ref().map_or("", |p| &p.name),
+                &home_score.to_string(),
+                &away_score.to_string(),
+            ])
+            .unwrap();
+    }
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "text/csv")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"game-{}.csv\"", id),
+        )
+        .body(Body::from(writer.into_inner().unwrap()))
+        .unwrap())
+}
+
+// Serves a printable report of a game
+async fn game_report_page_handler(
+    cookies: CookieJar,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Response<Body> {
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!("REDIRECT: missing or invalid auth cookie");
+        return Redirect::to("/login").into_response();
+    }
+    let Some(report) = read_game(&id).await else {
+        return (StatusCode::NOT_FOUND, Html("<h1>404 - Not Found</h1>")).into_response();
+    };
+
+    let home = &report.box_score.home;
+    let away = &report.box_score.away;
+
+    let mut periods_head = String::new();
+    let mut periods_home = String::new();
+    let mut periods_away = String::new();
+    for period in &report.periods {
+        periods_head += &format!("<th>{}</th>", period_label(period.period));
+        periods_home += &format!("<td>{}</td>", period.home);
+        periods_away += &format!("<td>{}</td>", period.away);
+    }
+
+    let mut players = String::new();
+    for team in [home, away] {
+        for player in &team.players {
+            players += &format!(
+                "<tr><td>{}</td><td>#{} {}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(&team.name),
+                player.number,
+                html_escape(&player.name),
+                player.points,
+                player.assists,
+                player.fouls
+            );
+        }
+    }
+
+    let mut events = String::new();
+    for event in &report.events {
+        let team = match event.side.as_str() {
+            "home" => &home.name,
+            _ => &away.name,
+        };
+        let description = match &event.action {
+            GameAction::Score {
+                kind,
+                points,
+                player,
+                assist,
+            } => {
+                let mut description = format!("{:+} {}", points, kind.as_str().replace('_', " "));
+                if let Some(player) = player {
+                    description += &format!(" by {}", player.label());
+                }
+                if let Some(assist) = assist {
+                    description += &format!(", assist {}", assist.label());
+                }
+                description
+            }
+            GameAction::Foul { player } => match player {
+                Some(player) => format!("foul by {}", player.label()),
+                None => String::from("foul"),
+            },
+        };
+
+        events += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            period_label(event.period),
+            event.clock,
+            html_escape(team),
+            html_escape(&description)
+        );
+    }
+
+    Html(format!(
+        include_str!("html/games/report.html"),
+        home_name = html_escape(&home.name),
+        away_name = html_escape(&away.name),
+        home_points = home.points,
+        away_points = away.points,
+        finished_at = format_unix_time(report.finished_at),
+        duration = format_duration(report.duration_secs),
+        id = report.id,
+        periods_head = periods_head,
+        periods_home = periods_home,
+        periods_away = periods_away,
+        home_fouls = home.fouls,
+        away_fouls = away.fouls,
+        players = players,
+        events = events,
+    ))
+    .into_response()
+}
+
+// Handles and returns the rows of the game history page
+async fn game_list_handler() -> Html<String> {
+    let mut html = String::new();
+
+    for game in game_summaries().await {
+        html += &format!(
+            "<tr><td>{}</td><td>{} {} - {} {}</td><td><a href=\"/games/{}\" target=\"_blank\">Report</a> <a href=\"/api/games/{}/csv\" download>CSV</a> <a href=\"/api/games/{}\" download>JSON</a></td></tr>",
+            format_unix_time(game.finished_at),
+            html_escape(&game.home_name),
+            game.home_points,
+            game.away_points,
+            html_escape(&game.away_name),
+            game.id,
+            game.id,
+            game.id,
+        );
+    }
+
+    Html(html)
+}
+
+// endregion: --- Game reports
+// region: --- Clock handlers
+
+// Sets the clock to a quick time in the path
+async fn quick_time_handler(axum::extract::Path((mins, secs)): axum::extract::Path<(u32, u32)>) {
+    *TIME_MINS.lock().await = mins;
+    *TIME_SECS.lock().await = secs;
+}
+
+// Handles and returns the time formatted as "mm:ss"
+async fn time_handler() -> Html<String> {
+    let time_mins = TIME_MINS.lock().await;
+    let time_secs = TIME_SECS.lock().await;
+    Html(format!("{}:{:02?}", *time_mins, *time_secs))
+}
+
+// Handles and returns the minutes of the time
+async fn dashboard_time_display_handler() -> Html<String> {
+    Html(format!(
+        "{}:{:02?}",
+        *TIME_MINS.lock().await,
+        *TIME_SECS.lock().await
+    ))
+}
+
+// Increases the minutes of the time by 1
+async fn mins_up_handler() {
+    let mut time_mins = TIME_MINS.lock().await;
+    *time_mins += 1;
+}
+
+// Decreases the minutes of the time by 1
+async fn mins_down_handler() {
+    let mut time_mins = TIME_MINS.lock().await;
+    if *time_mins > 0 {
+        *time_mins -= 1;
+    }
+}
+
+// Increases the seconds of the time by 1
+async fn secs_up_handler() {
+    let mut time_secs = TIME_SECS.lock().await;
+    if *time_secs < 59 {
+        *time_secs += 1;
+    } else {
+        let mut time_mins = TIME_MINS.lock().await;
+        *time_mins += 1;
+        *time_secs = 0;
+    }
+}
+
+// Decreases the seconds of the time by 1
+async fn secs_down_handler() {
+    let mut time_secs = TIME_SECS.lock().await;
+    let mut time_mins = TIME_MINS.lock().await;
+    if *time_secs > 0 {
+        *time_secs -= 1;
+    } else if *time_mins - 1 > 0 {
+        *time_mins -= 1;
+        *time_secs = 59;
+    }
+}
+
+// Ticks the clock down if the clock is not stopped
+async fn clock_ticker() {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let mut time_started = TIME_STARTED.lock().await;
+        if *time_started {
+            let mut time_mins = TIME_MINS.lock().await;
+            let mut time_secs = TIME_SECS.lock().await;
+            if *time_secs == 0 {
+                if *time_mins == 0 {
+                    *time_started = false;
+                } else {
+                    *time_mins -= 1;
+                    *time_secs = 59;
+                }
+            } else {
+                *time_secs -= 1;
+            }
+        }
+    }
+}
+
+// Starts the clock
+async fn tstart_handler() {
+    info!("TIMER: start");
+    let mut time_started = TIME_STARTED.lock().await;
+    *time_started = true;
+    GAME_STARTED_AT.lock().await.get_or_insert_with(unix_now);
+
+    // Sponsors that are only shown during breaks leave the screen
+    SPONSOR_ROLL_WAKE.notify_one();
+}
+
+// Stops the clock
+async fn tstop_handler() {
+    info!("TIMER: stop");
+    let mut time_started = TIME_STARTED.lock().await;
+    *time_started = false;
+}
+
+// endregion: --- Clock handlers
+// region: --- Quarter handlers
+
+// Handles and returns the current quarter formatted for the scoreboard
+async fn quarter_handler() -> Html<&'static str> {
+    let quarter = QUARTER.lock().await;
+    if *SHOW_QUARTER.lock().await {
+        if *quarter == 1 {
+            Html("1st")
+        } else if *quarter == 2 {
+            Html("2nd")
+        } else if *quarter == 3 {
+            Html("3rd")
+        } else if *quarter == 4 {
+            Html("4th")
+        } else {
+            Html("OVERTIME")
+        }
+    } else {
+        Html("")
+    }
+}
+
+// Handles the show quarter button
+async fn quarter_show_handler() {
+    let mut show_quarter = SHOW_QUARTER.lock().await;
+    *show_quarter = !*show_quarter;
+}
+
+// Handles and returns the css for the show quarter button
+async fn show_quarter_css_handler() -> Html<&'static str> {
+    let show_quarter = SHOW_QUARTER.lock().await;
+    let quarter = QUARTER.lock().await;
+
+    if *show_quarter {
+        if *quarter == 1 {
+            Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter1 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 2 {
+            Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter2 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 3 {
+            Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter3 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 4 {
+            Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter4 { background-color: rgb(227, 45, 32); } </style>")
+        } else {
+            Html("<style> #show-quarter { background-color: rgb(227, 45, 32); } #quarter5 { background-color: rgb(227, 45, 32); } </style>")
+        }
+    } else {
+        if *quarter == 1 {
+            Html("<style> #show-quarter { background-color: #e9981f; } #quarter1 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 2 {
+            Html("<style> #show-quarter { background-color: #e9981f; } #quarter2 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 3 {
+            Html("<style> #show-quarter { background-color: #e9981f; } #quarter3 { background-color: rgb(227, 45, 32); } </style>")
+        } else if *quarter == 4 {
+            Html("<style> #show-quarter { background-color: #e9981f; } #quarter4 { background-color: rgb(227, 45, 32); } </style>")
+        } else {
+            Html("<style> #show-quarter { background-color: #e9981f; } #quarter5 { background-color: rgb(227, 45, 32); } </style>")
+        }
+    }
+}
+
+// Changes the quarter, periods start at 1
+async fn quarter_change_handler(axum::extract::Path(q): axum::extract::Path<u8>) -> StatusCode {
+    if q == 0 {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    let mut quarter = QUARTER.lock().await;
+    *quarter = q;
+    StatusCode::OK
+}
+
+// endregion: --- Quarter handlers
+// region: --- Team library handlers
+
+// A single team of the library, matches are built by picking a home and an away team
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TeamInfoContainer {
+    name: String,
+    abbreviation: String,
+    primary_color: String,
+    secondary_color: String,
+}
+
+// The old preset format which stored a whole matchup, only read to migrate it
+#[derive(Deserialize)]
+struct LegacyMatchPreset {
+    home_name: String,
+    home_color: String,
+    away_name: String,
+    away_color: String,
+}
+
+// Team ids are generated from base62, anything else could escape the teams folder
+fn valid_team_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn generate_team_id() -> String {
+    const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+    let mut id = String::with_capacity(12);
+    for _ in 0..12 {
+        id.push(BASE62[thread_rng().gen_range(0..BASE62.len())] as char);
+    }
+    id
+}
+
+// Makes an abbreviation out of the initials of a team name, or its first letters if it is one word
+fn default_abbreviation(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+
+    let abbreviation: String = if words.len() > 1 {
+        words
+            .iter()
+            .filter_map(|w| w.chars().next())
+            .take(4)
+            .collect()
+    } else {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .take(3)
+            .collect()
+    };
+
+    abbreviation.to_uppercase()
+}
+
+// Returns the ids of every team in the library
+async fn team_ids() -> Vec<String> {
+    let mut teams = tokio::fs::read_dir("./teams").await.unwrap();
+    let mut valid_ids: Vec<String> = Vec::new();
+
+    while let Ok(Some(res)) = teams.next_entry().await {
+        if !res.file_name().to_string_lossy().to_string().contains(".") {
+            valid_ids.push(res.file_name().into_string().unwrap());
+        }
+    }
+
+    valid_ids
+}
+
+// Escapes text so it can be put into html, including attribute values
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// Splits old home/away presets into single teams and matches, the preset folders are only removed once the matches are saved
+async fn migrate_team_presets() {
+    let mut matches = MATCHES.lock().await.clone();
+    let migrated = migrate_legacy_presets("./teams", &mut matches).await;
+    if migrated.is_empty() {
+        return;
+    }
+
+    // The presets stay when the matches can't be saved, the next start reuses the teams made now
+    if let Err(err) = save_matches(&matches).await {
+        warn!(
+            "MIGRATE: could not save {}, the presets are migrated again on the next start: {}",
+            MATCHES_FILE, err
+        );
+        return;
+    }
+    *MATCHES.lock().await = matches;
+
+    for old_id in migrated {
+        info!("MIGRATE: preset {} is now a match", old_id);
+        if let Err(err) = tokio::fs::remove_dir_all(format!("./teams/{}", old_id)).await {
+            warn!("MIGRATE: could not remove preset {}: {}", old_id, err);
+        }
+    }
+}
+
+// Turns the legacy presets in a teams folder into teams and adds a match with the id of each preset, returns the ids of the presets that are matches now.
+// A team of a preset is only merged with a team that has the same name, color and logo, so different teams that share a name stay apart
+async fn migrate_legacy_presets(teams_dir: &str, matches: &mut Vec<SavedMatch>) -> Vec<String> {
+    let mut library: Vec<(String, TeamInfoContainer, Option<Vec<u8>>)> = Vec::new();
+    let mut legacy: Vec<(String, LegacyMatchPreset)> = Vec::new();
+
+    let Ok(mut entries) = tokio::fs::read_dir(teams_dir).await else {
+        return Vec::new();
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let id = entry.file_name().to_string_lossy().to_string();
+        if !valid_team_id(&id) {
+            continue;
+        }
+
+        let dir = format!("{}/{}", teams_dir, id);
+        let Ok(json) = tokio::fs::read_to_string(format!("{}/teaminfo.json", dir)).await else {
+            continue;
+        };
+
+        if let Ok(info) = serde_json::from_str::<TeamInfoContainer>(&json) {
+            let logo = read_logo_in(&dir).await;
+            library.push((id, info, logo));
+        } else if let Ok(preset) = serde_json::from_str::<LegacyMatchPreset>(&json) {
+            legacy.push((id, preset));
+        } else {
+            warn!("MIGRATE: could not read team {}, skipping", id);
+        }
+    }
+    // Always migrates in the same order, so a retried migration merges the same teams
+    legacy.sort_by(|a, b| a.0.cmp(&b.0));
+    library.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut migrated = Vec::new();
+    for (old_id, preset) in legacy {
+        // The match was saved by an earlier start that stopped before the preset was removed
+        if matches.iter().any(|m| m.id == old_id) {
+            migrated.push(old_id);
+            continue;
+        }
+
+        let mut team_ids = Vec::new();
+        for (side, name, color) in [
+            ("home", preset.home_name.clone(), preset.home_color.clone()),
+            ("away", preset.away_name.clone(), preset.away_color.clone()),
+        ] {
+            let logo = tokio::fs::read(format!("{}/{}/{}.png", teams_dir, old_id, side))
+                .await
+                .ok();
+            let primary_color =
+                normalize_color(&color).unwrap_or_else(|| hex_color(TeamColors::default().primary));
+
+            if let Some((id, _, _)) = library.iter().find(|(_, info, existing)| {
+                info.name == name && info.primary_color == primary_color && *existing == logo
+            }) {
+                team_ids.push(id.clone());
+                continue;
+            }
+
+            let info = TeamInfoContainer {
+                abbreviation: default_abbreviation(&name),
+                name,
+                primary_color,
+                secondary_color: hex_color(TeamColors::default().secondary),
+            };
+            let id = generate_team_id();
+
+            let team_logo = logo.clone().map(|logo| (ImageKind::Png, logo));
+            if let Err(err) =
+                create_team_dir(&format!("{}/{}", teams_dir, id), &info, team_logo).await
+            {
+                warn!(
+                    "MIGRATE: could not save the {} team of preset {}, it is migrated again on the next start: {}",
+                    side, old_id, err
+                );
+                break;
+            }
+
+            info!(
+                "MIGRATE: {} team of preset {} is now team {}",
+                side, old_id, id
+            );
+            team_ids.push(id.clone());
+            library.push((id, info, logo));
+        }
+
+        // The match keeps the id of the preset, so links to the preset API keep working
+        let [home, away] = team_ids.as_slice() else {
+            continue;
+        };
+        matches.push(SavedMatch {
+            id: old_id.clone(),
+            home: home.clone(),
+            away: away.clone(),
+        });
+        migrated.push(old_id);
+    }
+
+    migrated
 }
 
 async fn team_selectors_handler() -> Html<String> {
     let mut inject_html = String::new();
-    let mut team_presets = tokio::fs::read_dir("./teams").await.unwrap();
-    let mut valid_ids: Vec<String> = Vec::new();
+    let valid_ids = team_ids().await;
+
+    for i in &valid_ids {
+        let Some(team_info) = read_team(i).await else {
+            continue;
+        };
+
+        let logo_bytes = read_team_logo(i).await.unwrap_or_default();
+
+        let mut roster_html = String::new();
+        for player in read_roster(i).await {
+            roster_html += &format!(
+                "<li>{} {}</li>",
+                html_escape(&player.label()),
+                html_escape(&player.position)
+            );
+        }
+
+        inject_html += &format!(
+            "
+            <div class=\"match-selector\">
+                <p>{} ({})</p>
+                <div style=\"display: inline\">
+                    <img src=\"{}\" height=\"30px\" width=\"auto\" style=\"margin-right: 15px;\"/>
+                    <span style=\"display: inline-block; width: 20px; height: 20px; background-color: {};\"></span>
+                    <span style=\"display: inline-block; width: 20px; height: 20px; background-color: {};\"></span>
+                </div>
+                <br>
+                <button hx-post=\"/load_team/home/{}\" hx-swap=\"none\" style=\"width: 49%;\">Home</button>
+                <button hx-post=\"/load_team/away/{}\" hx-swap=\"none\" style=\"width: 49%;\">Away</button>
+                <button hx-post=\"/delete_team/{}\" hx-swap=\"none\" style=\"width: 100%; margin-top: 15px;\">Remove</button>
+                <details class=\"preset-edit\">
+                    <summary>Edit</summary>
+                    <form data-id=\"{}\" onsubmit=\"saveTeam(this); return false;\">
+                        <label>Team Name:</label>
+                        <input type=\"text\" name=\"name\" value=\"{}\">
+                        <label>Abbreviation:</label>
+                        <input type=\"text\" name=\"abbreviation\" value=\"{}\">
+                        <label>Primary Jersey Color:</label>
+                        <input type=\"color\" name=\"primary_color\" value=\"{}\">
+                        <label>Secondary Jersey Color:</label>
+                        <input type=\"color\" name=\"secondary_color\" value=\"{}\">
+                        <label>Team Logo:</label>
+                        <input type=\"file\" name=\"logo\" accept=\"image/png,image/jpeg,image/webp,image/svg+xml\">
+                        <input type=\"submit\" value=\"Save\" class=\"submit-button\">
+                    </form>
+                    <label>Roster:</label>
+                    <ul>{}</ul>
+                    <label>Replace roster from CSV (number, name, position):</label>
+                    <input type=\"file\" accept=\".csv,text/csv\" onchange=\"importRoster(this, '{}')\">
+                </details>
+            </div>
+        ",
+            html_escape(&team_info.name),
+            html_escape(&team_info.abbreviation),
+            cache_image(format!("team:{}", i), logo_bytes).await,
+            html_escape(&team_info.primary_color),
+            html_escape(&team_info.secondary_color),
+            i,
+            i,
+            i,
+            i,
+            html_escape(&team_info.name),
+            html_escape(&team_info.abbreviation),
+            html_escape(&team_info.primary_color),
+            html_escape(&team_info.secondary_color),
+            roster_html,
+            i,
+        );
+    }
+
+    debug!("TEAMS: {:?}", valid_ids);
+
+    Html::from(inject_html)
+}
+
+// Loads a team from the library as the home or away team
+async fn load_team_handler(
+    axum::extract::Path((side, id)): axum::extract::Path<(String, String)>,
+) -> StatusCode {
+    let Some(team_info) = read_team(&id).await else {
+        warn!("FAIL: cannot load {}, doesnt exist!", id);
+        return StatusCode::NOT_FOUND;
+    };
+
+    let (name, img_data, colors, team_id) = match side.as_str() {
+        "home" => (&*HOME_NAME, &*HOME_IMG_DATA, &*HOME_COLORS, &*HOME_TEAM_ID),
+        "away" => (&*AWAY_NAME, &*AWAY_IMG_DATA, &*AWAY_COLORS, &*AWAY_TEAM_ID),
+        _ => return StatusCode::NOT_FOUND,
+    };
+
+    info!("LOAD: {} team {:?}", side, team_info);
+
+    *team_id.lock().await = Some(id.clone());
+    *colors.lock().await = TeamColors::from_team(&team_info);
+    *name.lock().await = team_info.name;
+    let logo = read_team_logo(&id).await.unwrap_or_default();
+    if logo.is_empty() {
+        release_image(&side).await;
+    } else {
+        cache_image(side.clone(), logo.clone()).await;
+    }
+    *img_data.lock().await = logo;
+
+    StatusCode::OK
+}
+
+// Fields of the add team form, anything else is rejected
+const TEAM_FORM_FIELDS: [&str; 5] = [
+    "name",
+    "abbreviation",
+    "primary_color",
+    "secondary_color",
+    "logo",
+];
+// Longest text field accepted in the add team form
+const TEAM_FORM_TEXT_MAX: usize = 256;
+
+// Handles the form that adds a team to the library, nothing is written until every field is valid
+async fn add_team_handler(mut payload: Multipart) -> StatusCode {
+    let mut fields: HashMap<String, axum::body::Bytes> = HashMap::new();
+
+    // Loops through the fields of the form, the body size is capped by max_upload_mb
+    loop {
+        let field = match payload.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                warn!("FAIL: team upload: {}", err.body_text());
+                return err.status();
+            }
+        };
+
+        let name = field.name().unwrap_or_default().to_string();
+        if !TEAM_FORM_FIELDS.contains(&name.as_str()) || fields.contains_key(&name) {
+            warn!("FAIL: team upload has an unexpected field {:?}", name);
+            return StatusCode::BAD_REQUEST;
+        }
+
+        let data = match field.bytes().await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("FAIL: team upload: {}", err.body_text());
+                return err.status();
+            }
+        };
+
+        if name != "logo" && data.len() > TEAM_FORM_TEXT_MAX {
+            warn!("FAIL: team upload field {} is too long", name);
+            return StatusCode::PAYLOAD_TOO_LARGE;
+        }
+
+        fields.insert(name, data);
+    }
+
+    // Text fields have to be utf-8, missing ones are empty
+    let mut text = |name: &str| -> Result<String, StatusCode> {
+        match fields.remove(name) {
+            Some(data) => String::from_utf8(data.to_vec())
+                .map(|t| t.trim().to_string())
+                .map_err(|_| StatusCode::BAD_REQUEST),
+            None => Ok(String::new()),
+        }
+    };
+
+    let (Ok(team_name), Ok(mut abbreviation), Ok(primary_color), Ok(secondary_color)) = (
+        text("name"),
+        text("abbreviation"),
+        text("primary_color"),
+        text("secondary_color"),
+    ) else {
+        warn!("FAIL: team upload has a field that isn't text");
+        return StatusCode::BAD_REQUEST;
+    };
+
+    if team_name.is_empty() {
+        warn!("FAIL: team upload is missing a name");
+        return StatusCode::BAD_REQUEST;
+    }
+
+    if abbreviation.is_empty() {
+        abbreviation = default_abbreviation(&team_name);
+    }
+
+    let (Some(primary_color), Some(secondary_color)) = (
+        normalize_color(&primary_color),
+        normalize_color(&secondary_color),
+    ) else {
+        warn!(
+            "FAIL: invalid team colors {} / {}",
+            primary_color, secondary_color
+        );
+        return StatusCode::BAD_REQUEST;
+    };
+
+    // An empty file field means no logo was picked
+    let logo = match fields.remove("logo").filter(|l| !l.is_empty()) {
+        Some(logo) => {
+            info!("LOGO: recieved logo\n\tLENGTH: {}", logo.len());
+            let Some(logo) = process_image(logo.to_vec()).await else {
+                warn!("FAIL: logo is not a png, jpeg, webp or svg image");
+                return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+            };
+            Some(logo)
+        }
+        None => None,
+    };
+
+    let info_container = TeamInfoContainer {
+        name: team_name,
+        abbreviation,
+        primary_color,
+        secondary_color,
+    };
+
+    debug!("TEAM: {:?}", info_container);
+
+    // The team is written to a hidden folder first and renamed, so a failed write leaves nothing in the library
+    let id = unused_team_id();
+    let tmp_dir = format!("./teams/.tmp-{}", id);
+
+    let created = match create_team_dir(&tmp_dir, &info_container, logo).await {
+        Ok(()) => tokio::fs::rename(&tmp_dir, format!("./teams/{}", id)).await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = created {
+        warn!("FAIL: could not save team {}: {}", id, err);
+        let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!("TEAM: added {}", id);
+    StatusCode::OK
+}
+
+// Writes the info and logo of a new team into a folder
+async fn create_team_dir(
+    dir: &str,
+    info: &TeamInfoContainer,
+    logo: Option<(ImageKind, Vec<u8>)>,
+) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+
+    if let Some((kind, logo)) = logo {
+        tokio::fs::write(format!("{}/{}", dir, kind.logo_file_name()), logo).await?;
+    }
+
+    let json = serde_json::to_string(info).expect("Failed to serialize team info");
+    tokio::fs::write(format!("{}/teaminfo.json", dir), json).await
+}
+
+// Removes the hidden folders of team uploads that were interrupted
+async fn clean_team_uploads() {
+    let mut entries = tokio::fs::read_dir("./teams").await.unwrap();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_name().to_string_lossy().starts_with(".tmp-") {
+            warn!(
+                "TEAM: removing interrupted upload {}",
+                entry.file_name().to_string_lossy()
+            );
+            let _ = tokio::fs::remove_dir_all(entry.path()).await;
+        }
+    }
+}
+
+async fn delete_team_handler(axum::extract::Path(id): axum::extract::Path<String>) -> StatusCode {
+    let id_path = format!("teams/{}", id);
+    if valid_team_id(&id) && std::path::Path::new(&id_path).is_dir() {
+        info!("REMOVE: {}", id);
+        tokio::fs::remove_dir_all(id_path)
+            .await
+            .expect("Could not delete id!");
+        release_image(&format!("team:{}", id)).await;
+        // A match without one of its teams can't be loaded anymore
+        match remove_team_matches(&id).await {
+            Ok(()) => StatusCode::OK,
+            Err(status) => status,
+        }
+    } else {
+        warn!("FAIL: cannot delete {}, doesnt exist!", id);
+        StatusCode::NOT_FOUND
+    }
+}
+
+// endregion: --- Team library handlers
+// region: --- Team API
+
+// A team as returned by the team API, the logo is linked instead of embedded
+#[derive(Serialize)]
+struct TeamApiInfo {
+    id: String,
+    #[serde(flatten)]
+    info: TeamInfoContainer,
+    logo: String,
+}
+
+impl TeamApiInfo {
+    fn new(id: String, info: TeamInfoContainer) -> TeamApiInfo {
+        TeamApiInfo {
+            logo: format!("/api/teams/{}/logo", id),
+            id,
+            info,
+        }
+    }
+}
+
+// Fields of a team that a PATCH request may change, missing fields are left alone
+#[derive(Deserialize)]
+struct TeamInfoPatch {
+    name: Option<String>,
+    abbreviation: Option<String>,
+    primary_color: Option<String>,
+    secondary_color: Option<String>,
+}
+
+// Reads the info of a team, None if it doesn't exist
+async fn read_team(id: &str) -> Option<TeamInfoContainer> {
+    if !valid_team_id(id) {
+        return None;
+    }
+
+    let team_info_json = tokio::fs::read_to_string(format!("./teams/{}/teaminfo.json", id))
+        .await
+        .ok()?;
+    serde_json::from_str(&team_info_json).ok()
+}
+
+async fn write_team(id: &str, info: &TeamInfoContainer) -> Result<(), StatusCode> {
+    let json = serde_json::to_string(info).expect("Failed to serialize team info");
+    tokio::fs::write(Path::new(&format!("./teams/{}/teaminfo.json", id)), json)
+        .await
+        .map_err(|err| {
+            warn!("FAIL: could not save team {}: {}", id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// Trims the name and abbreviation of a team and checks them like the add team form does, an empty abbreviation is made from the name
+fn validate_team_text(info: &mut TeamInfoContainer) -> Result<(), StatusCode> {
+    info.name = info.name.trim().to_string();
+    info.abbreviation = info.abbreviation.trim().to_string();
+
+    if info.name.is_empty()
+        || info.name.len() > TEAM_FORM_TEXT_MAX
+        || info.abbreviation.len() > TEAM_FORM_TEXT_MAX
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if info.abbreviation.is_empty() {
+        info.abbreviation = default_abbreviation(&info.name);
+    }
+    Ok(())
+}
+
+// Lists every team without its logo
+async fn list_teams_handler() -> Json<Vec<TeamApiInfo>> {
+    let mut teams = Vec::new();
+
+    for id in team_ids().await {
+        if let Some(info) = read_team(&id).await {
+            teams.push(TeamApiInfo::new(id, info));
+        }
+    }
+
+    Json(teams)
+}
+
+async fn get_team_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<TeamApiInfo>, StatusCode> {
+    let info = read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(TeamApiInfo::new(id, info)))
+}
+
+// Replaces all info fields of a team
+async fn put_team_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(mut info): Json<TeamInfoContainer>,
+) -> Result<Json<TeamApiInfo>, StatusCode> {
+    read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    validate_team_text(&mut info)?;
+    info.primary_color = normalize_color(&info.primary_color).ok_or(StatusCode::BAD_REQUEST)?;
+    info.secondary_color = normalize_color(&info.secondary_color).ok_or(StatusCode::BAD_REQUEST)?;
+
+    info!("TEAM: update {}", id);
+    write_team(&id, &info).await?;
+    Ok(Json(TeamApiInfo::new(id, info)))
+}
+
+// Updates only the info fields present in the request
+async fn patch_team_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(patch): Json<TeamInfoPatch>,
+) -> Result<Json<TeamApiInfo>, StatusCode> {
+    let mut info = read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    if let Some(name) = patch.name {
+        info.name = name;
+    }
+    if let Some(abbreviation) = patch.abbreviation {
+        info.abbreviation = abbreviation;
+    }
+    if let Some(primary_color) = patch.primary_color {
+        info.primary_color = normalize_color(&primary_color).ok_or(StatusCode::BAD_REQUEST)?;
+    }
+    if let Some(secondary_color) = patch.secondary_color {
+        info.secondary_color = normalize_color(&secondary_color).ok_or(StatusCode::BAD_REQUEST)?;
+    }
+    validate_team_text(&mut info)?;
+
+    info!("TEAM: update {}", id);
+    write_team(&id, &info).await?;
+    Ok(Json(TeamApiInfo::new(id, info)))
+}
+
+async fn team_logo_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Response<Body>, StatusCode> {
+    read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    let logo = read_team_logo(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(image_response(logo))
+}
+
+// Replaces the logo of a team with the image in the request body
+async fn replace_team_logo_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    logo: axum::body::Bytes,
+) -> StatusCode {
+    if read_team(&id).await.is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+    let Some((kind, logo)) = process_image(logo.to_vec()).await else {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    };
+
+    info!("LOGO: replace logo of {}, LENGTH: {}", id, logo.len());
+    match write_team_logo(&format!("./teams/{}", id), kind, &logo).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+// endregion: --- Team API
+// region: --- Saved matches
+
+// A saved pairing of two teams from the library, the preset API reads and edits matches
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SavedMatch {
+    id: String,
+    home: String,
+    away: String,
+}
+
+async fn load_matches() {
+    let Ok(json) = tokio::fs::read_to_string(MATCHES_FILE).await else {
+        return;
+    };
+
+    match serde_json::from_str::<Vec<SavedMatch>>(&json) {
+        Ok(matches) => *MATCHES.lock().await = matches,
+        Err(err) => warn!("MATCH: ignoring invalid {}: {}", MATCHES_FILE, err),
+    }
+}
+
+async fn save_matches(matches: &[SavedMatch]) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(matches).expect("Failed to serialize matches");
+    tokio::fs::write(MATCHES_FILE, json).await
+}
+
+// Saves a changed copy of the matches and only keeps the change once it is written, a failed write is a 500
+async fn store_matches(
+    matches: &mut Vec<SavedMatch>,
+    updated: Vec<SavedMatch>,
+) -> Result<(), StatusCode> {
+    save_matches(&updated).await.map_err(|err| {
+        warn!("FAIL: could not save {}: {}", MATCHES_FILE, err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    *matches = updated;
+    Ok(())
+}
+
+// Generates a match id that isn't used by a match or a team yet
+fn unused_match_id(matches: &[SavedMatch]) -> String {
+    loop {
+        let id = unused_team_id();
+        if !matches.iter().any(|m| m.id == id) {
+            return id;
+        }
+    }
+}
+
+async fn find_match(id: &str) -> Option<SavedMatch> {
+    MATCHES.lock().await.iter().find(|m| m.id == id).cloned()
+}
+
+// Removes the matches a deleted team was part of
+async fn remove_team_matches(team_id: &str) -> Result<(), StatusCode> {
+    let mut matches = MATCHES.lock().await;
+    let mut updated = matches.clone();
+    updated.retain(|m| m.home != team_id && m.away != team_id);
+
+    if updated.len() != matches.len() {
+        info!(
+            "MATCH: removed {} matches of team {}",
+            matches.len() - updated.len(),
+            team_id
+        );
+        store_matches(&mut matches, updated).await?;
+    }
+    Ok(())
+}
+
+// A match as the preset API returns it, the same fields presets had before teams were split into a library
+#[derive(Serialize)]
+struct PresetInfo {
+    id: String,
+    home_team: String,
+    away_team: String,
+    home_name: String,
+    home_color: String,
+    away_name: String,
+    away_color: String,
+    home_logo: String,
+    away_logo: String,
+}
+
+impl PresetInfo {
+    fn new(saved: &SavedMatch, home: TeamInfoContainer, away: TeamInfoContainer) -> PresetInfo {
+        PresetInfo {
+            id: saved.id.clone(),
+            home_team: saved.home.clone(),
+            away_team: saved.away.clone(),
+            home_name: home.name,
+            home_color: home.primary_color,
+            away_name: away.name,
+            away_color: away.primary_color,
+            home_logo: format!("/api/presets/{}/logo/home", saved.id),
+            away_logo: format!("/api/presets/{}/logo/away", saved.id),
+        }
+    }
+}
+
+// Reads the teams of a match, None if the match or one of its teams doesn't exist
+async fn read_preset(id: &str) -> Option<PresetInfo> {
+    let saved = find_match(id).await?;
+    let home = read_team(&saved.home).await?;
+    let away = read_team(&saved.away).await?;
+    Some(PresetInfo::new(&saved, home, away))
+}
+
+// Creates a match out of two teams of the library
+#[derive(Deserialize)]
+struct NewPreset {
+    home_team: String,
+    away_team: String,
+}
+
+// Fields of a match that a PUT or PATCH may change. Names and colors are written to the teams of the match,
+// home_team and away_team pick other teams
+#[derive(Deserialize)]
+struct PresetPatch {
+    home_team: Option<String>,
+    away_team: Option<String>,
+    home_name: Option<String>,
+    home_color: Option<String>,
+    away_name: Option<String>,
+    away_color: Option<String>,
+}
+
+// Adds a match with the teams in the request
+async fn create_match(home: String, away: String) -> Result<SavedMatch, StatusCode> {
+    if read_team(&home).await.is_none() || read_team(&away).await.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let mut matches = MATCHES.lock().await;
+    let saved = SavedMatch {
+        id: unused_match_id(&matches),
+        home,
+        away,
+    };
+    let mut updated = matches.clone();
+    updated.push(saved.clone());
+    store_matches(&mut matches, updated).await?;
+
+    info!(
+        "MATCH: saved {} ({} vs {})",
+        saved.id, saved.home, saved.away
+    );
+    Ok(saved)
+}
+
+// Lists every match without the team logos
+async fn list_presets_handler() -> Json<Vec<PresetInfo>> {
+    let ids: Vec<String> = MATCHES.lock().await.iter().map(|m| m.id.clone()).collect();
+    let mut presets = Vec::new();
+
+    for id in ids {
+        if let Some(preset) = read_preset(&id).await {
+            presets.push(preset);
+        }
+    }
+
+    Json(presets)
+}
+
+async fn get_preset_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<PresetInfo>, StatusCode> {
+    read_preset(&id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn create_preset_handler(
+    Json(preset): Json<NewPreset>,
+) -> Result<(StatusCode, Json<PresetInfo>), StatusCode> {
+    let saved = create_match(preset.home_team, preset.away_team).await?;
+    let preset = read_preset(&saved.id).await.ok_or(StatusCode::NOT_FOUND)?;
+    Ok((StatusCode::CREATED, Json(preset)))
+}
+
+// Applies a change to a match and its teams, nothing is changed unless every field is valid and every file could be written
+async fn update_preset(id: String, patch: PresetPatch) -> Result<Json<PresetInfo>, StatusCode> {
+    let mut saved = find_match(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(home) = patch.home_team {
+        saved.home = home;
+    }
+    if let Some(away) = patch.away_team {
+        saved.away = away;
+    }
+
+    let mut home = read_team(&saved.home).await.ok_or(StatusCode::NOT_FOUND)?;
+    let mut away = read_team(&saved.away).await.ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(name) = patch.home_name {
+        home.name = name;
+    }
+    if let Some(color) = patch.home_color {
+        home.primary_color = normalize_color(&color).ok_or(StatusCode::BAD_REQUEST)?;
+    }
+    if let Some(name) = patch.away_name {
+        away.name = name;
+    }
+    if let Some(color) = patch.away_color {
+        away.primary_color = normalize_color(&color).ok_or(StatusCode::BAD_REQUEST)?;
+    }
+    validate_team_text(&mut home)?;
+    validate_team_text(&mut away)?;
+
+    // The teams are staged next to their info and only moved into place once the match is saved
+    let staged = [(&saved.home, &home), (&saved.away, &away)];
+    let unstage = || async {
+        for (team_id, _) in staged {
+            let _ = tokio::fs::remove_file(format!("./teams/{}/teaminfo.json.tmp", team_id)).await;
+        }
+    };
+    for (team_id, info) in staged {
+        let json = serde_json::to_string(info).expect("Failed to serialize team info");
+        if let Err(err) =
+            tokio::fs::write(format!("./teams/{}/teaminfo.json.tmp", team_id), json).await
+        {
+            warn!("FAIL: could not save team {}: {}", team_id, err);
+            unstage().await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let mut matches = MATCHES.lock().await;
+    let Some(index) = matches.iter().position(|m| m.id == id) else {
+        unstage().await;
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let mut updated = matches.clone();
+    updated[index] = saved.clone();
+    if let Err(status) = store_matches(&mut matches, updated).await {
+        unstage().await;
+        return Err(status);
+    }
+    drop(matches);
+
+    info!("MATCH: update {}", id);
+    for (team_id, _) in staged {
+        let team_dir = format!("./teams/{}", team_id);
+        if let Err(err) = tokio::fs::rename(
+            format!("{}/teaminfo.json.tmp", team_dir),
+            format!("{}/teaminfo.json", team_dir),
+        )
+        .await
+        {
+            // Both sides are the same team when the match is a team against itself
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("FAIL: could not save team {}: {}", team_id, err);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    Ok(Json(PresetInfo::new(&saved, home, away)))
+}
+
+// Replaces the names and colors of both teams of a match
+async fn put_preset_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(preset): Json<LegacyMatchPreset>,
+) -> Result<Json<PresetInfo>, StatusCode> {
+    update_preset(
+        id,
+        PresetPatch {
+            home_team: None,
+            away_team: None,
+            home_name: Some(preset.home_name),
+            home_color: Some(preset.home_color),
+            away_name: Some(preset.away_name),
+            away_color: Some(preset.away_color),
+        },
+    )
+    .await
+}
+
+// Updates only the fields present in the request
+async fn patch_preset_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(patch): Json<PresetPatch>,
+) -> Result<Json<PresetInfo>, StatusCode> {
+    update_preset(id, patch).await
+}
+
+// Removes a match, its teams stay in the library
+async fn delete_preset_handler(axum::extract::Path(id): axum::extract::Path<String>) -> StatusCode {
+    let mut matches = MATCHES.lock().await;
+    let Some(index) = matches.iter().position(|m| m.id == id) else {
+        return StatusCode::NOT_FOUND;
+    };
+    let mut updated = matches.clone();
+    updated.remove(index);
+    if let Err(status) = store_matches(&mut matches, updated).await {
+        return status;
+    }
+
+    info!("MATCH: removed {}", id);
+    StatusCode::NO_CONTENT
+}
+
+// Maps the side in a logo route to the team of the match
+async fn preset_team_id(id: &str, side: &str) -> Option<String> {
+    let saved = find_match(id).await?;
+    match side {
+        "home" => Some(saved.home),
+        "away" => Some(saved.away),
+        _ => None,
+    }
+}
+
+async fn preset_logo_handler(
+    axum::extract::Path((id, side)): axum::extract::Path<(String, String)>,
+) -> Result<Response<Body>, StatusCode> {
+    let team_id = preset_team_id(&id, &side)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    team_logo_handler(axum::extract::Path(team_id)).await
+}
+
+// Replaces the logo of one team of a match
+async fn replace_preset_logo_handler(
+    axum::extract::Path((id, side)): axum::extract::Path<(String, String)>,
+    logo: axum::body::Bytes,
+) -> StatusCode {
+    match preset_team_id(&id, &side).await {
+        Some(team_id) => replace_team_logo_handler(axum::extract::Path(team_id), logo).await,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+// Handles and returns the saved matches of the team info page
+async fn match_list_handler() -> Html<String> {
+    let mut html = String::new();
+
+    for preset in list_presets_handler().await.0 {
+        html += &format!(
+            "
+            <div class=\"match-entry\">
+                <span>{} vs {}</span>
+                <button hx-post=\"/load_match/{}\" hx-swap=\"none\">Load</button>
+                <button hx-post=\"/delete_match/{}\" hx-target=\"#match-list\" hx-confirm=\"Remove this match? Its teams stay in the library.\">Remove</button>
+            </div>
+            ",
+            html_escape(&preset.home_name),
+            html_escape(&preset.away_name),
+            preset.id,
+            preset.id,
+        );
+    }
+
+    if html.is_empty() {
+        html = String::from("<p>No saved matches yet.</p>");
+    }
+
+    Html(html)
+}
+
+// Saves the loaded home and away teams as a match
+async fn save_match_handler() -> Result<Html<String>, StatusCode> {
+    let home = HOME_TEAM_ID.lock().await.clone();
+    let away = AWAY_TEAM_ID.lock().await.clone();
+    let (Some(home), Some(away)) = (home, away) else {
+        warn!("FAIL: cannot save a match without a loaded home and away team");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    create_match(home, away).await?;
+    Ok(match_list_handler().await)
+}
+
+// Loads both teams of a match
+async fn load_match_handler(axum::extract::Path(id): axum::extract::Path<String>) -> StatusCode {
+    let Some(saved) = find_match(&id).await else {
+        warn!("FAIL: cannot load match {}, doesnt exist!", id);
+        return StatusCode::NOT_FOUND;
+    };
+
+    info!("LOAD: match {}", id);
+    let home = load_team_handler(axum::extract::Path((String::from("home"), saved.home))).await;
+    if home != StatusCode::OK {
+        return home;
+    }
+    load_team_handler(axum::extract::Path((String::from("away"), saved.away))).await
+}
+
+async fn delete_match_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    match delete_preset_handler(axum::extract::Path(id)).await {
+        StatusCode::NO_CONTENT => Ok(match_list_handler().await),
+        status => Err(status),
+    }
+}
+
+// endregion: --- Saved matches
+// region: --- Team bundles
+
+// Files a team folder in a bundle may contain, besides the headshots folder
+const BUNDLE_TEAM_FILES: [&str; 4] = ["teaminfo.json", "roster.json", "logo.png", "logo.svg"];
+// The saved matches of a bundle, next to the team folders
+const BUNDLE_MATCHES_FILE: &str = "matches.json";
+// Bundles with more files than this are refused before anything is unpacked
+const BUNDLE_MAX_ENTRIES: usize = 5000;
+// All files of a bundle together may unpack to this many times max_upload_mb
+const BUNDLE_MAX_TOTAL_FACTOR: u64 = 10;
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    // Comma separated team ids, every team is exported if it is missing
+    ids: Option<String>,
+}
+
+// A team read from a bundle before it is validated
+#[derive(Default, Debug)]
+struct BundleTeam {
+    teaminfo: Option<Vec<u8>>,
+    roster: Option<Vec<u8>>,
+    logo: Option<(String, Vec<u8>)>,
+    headshots: Vec<(String, Vec<u8>)>,
+}
+
+// The teams and matches of a bundle before they are validated
+#[derive(Default, Debug)]
+struct Bundle {
+    teams: Vec<(String, BundleTeam)>,
+    matches: Option<Vec<u8>>,
+}
+
+// How much a bundle may unpack to
+struct BundleLimits {
+    max_file_size: u64,
+    max_total_size: u64,
+    max_entries: usize,
+}
+
+// Generates a team id that isn't used in the library yet
+fn unused_team_id() -> String {
+    loop {
+        let id = generate_team_id();
+        if !Path::new(&format!("./teams/{}", id)).exists() {
+            return id;
+        }
+    }
+}
+
+// Splits the ids of an export query
+fn export_ids(ids: &str) -> Vec<String> {
+    ids.split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+// Handles and returns a zip of the selected teams, each team is a folder named after its id
+async fn export_teams_handler(
+    axum::extract::Query(query): axum::extract::Query<ExportQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    let ids = match query.ids {
+        Some(ids) => export_ids(&ids),
+        None => team_ids().await,
+    };
+
+    export_bundle(ids, Vec::new(), "froggi-teams.zip").await
+}
+
+// Handles and returns a zip of the selected matches with their teams
+async fn export_presets_handler(
+    axum::extract::Query(query): axum::extract::Query<ExportQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    let all_matches = MATCHES.lock().await.clone();
+    let matches: Vec<SavedMatch> = match query.ids {
+        Some(ids) => {
+            let mut matches = Vec::new();
+            for id in export_ids(&ids) {
+                let saved = all_matches.iter().find(|m| m.id == id);
+                matches.push(saved.cloned().ok_or(StatusCode::NOT_FOUND)?);
+            }
+            matches
+        }
+        None => all_matches,
+    };
+
+    let mut ids: Vec<String> = Vec::new();
+    for saved in &matches {
+        for id in [&saved.home, &saved.away] {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+    }
+
+    export_bundle(ids, matches, "froggi-presets.zip").await
+}
+
+// Zips teams and the matches between them the way import_bundle reads them
+async fn export_bundle(
+    ids: Vec<String>,
+    matches: Vec<SavedMatch>,
+    file_name: &str,
+) -> Result<Response<Body>, StatusCode> {
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+
+    if !matches.is_empty() {
+        let json = serde_json::to_vec_pretty(&matches).expect("Failed to serialize matches");
+        files.push((String::from(BUNDLE_MATCHES_FILE), json));
+    }
+
+    for id in &ids {
+        read_team(id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+        for file_name in BUNDLE_TEAM_FILES {
+            if let Ok(data) = tokio::fs::read(format!("./teams/{}/{}", id, file_name)).await {
+                files.push((format!("{}/{}", id, file_name), data));
+            }
+        }
+
+        if let Ok(mut headshots) = tokio::fs::read_dir(format!("./teams/{}/headshots", id)).await {
+            while let Ok(Some(entry)) = headshots.next_entry().await {
+                let data = tokio::fs::read(entry.path()).await.unwrap();
+                files.push((
+                    format!("{}/headshots/{}", id, entry.file_name().to_string_lossy()),
+                    data,
+                ));
+            }
+        }
+    }
+
+    let zip = tokio::task::spawn_blocking(move || -> zip::result::ZipResult<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        for (path, data) in files {
+            writer.start_file(path, options)?;
+            io::Write::write_all(&mut writer, &data)?;
+        }
+
+        Ok(writer.finish()?.into_inner())
+    })
+    .await
+    .unwrap()
+    .map_err(|err| {
+        warn!("FAIL: could not export teams: {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("EXPORT: {} teams, {} matches", ids.len(), matches.len());
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/zip")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(Body::from(zip))
+        .unwrap())
+}
+
+// Reads the team folders and matches out of a zip, anything that isn't a known team file is rejected.
+// Reading stops as soon as the bundle unpacks to more than the limits allow, so a zip bomb can't fill the memory
+fn read_bundle(bundle: &[u8], limits: &BundleLimits) -> Option<Bundle> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bundle)).ok()?;
+    if archive.len() > limits.max_entries {
+        return None;
+    }
+
+    let mut result = Bundle::default();
+    let teams = &mut result.teams;
+    let mut total_size: u64 = 0;
+
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).ok()?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let path = entry.enclosed_name()?;
+        let parts: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let matches_file = matches!(&parts[..], [file_name] if file_name == BUNDLE_MATCHES_FILE);
+        let (dir, file_name, headshot) = match &parts[..] {
+            _ if matches_file => (&parts[0], &parts[0], false),
+            [dir, file_name] if BUNDLE_TEAM_FILES.contains(&file_name.as_str()) => {
+                (dir, file_name, false)
+            }
+            [dir, headshots, file_name] if headshots == "headshots" => (dir, file_name, true),
+            _ => return None,
+        };
+
+        // The sizes in the zip can't be trusted, so the limits are checked on what is actually unpacked
+        let max_size = limits.max_file_size.min(limits.max_total_size - total_size);
+        if entry.size() > max_size {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        io::Read::read_to_end(&mut io::Read::take(entry, max_size + 1), &mut data).ok()?;
+        if data.len() as u64 > max_size {
+            return None;
+        }
+        total_size += data.len() as u64;
+
+        if matches_file {
+            if result.matches.replace(data).is_some() {
+                return None;
+            }
+            continue;
+        }
+
+        let index = match teams.iter().position(|(d, _)| d == dir) {
+            Some(index) => index,
+            None => {
+                teams.push((dir.clone(), BundleTeam::default()));
+                teams.len() - 1
+            }
+        };
+        let team = &mut teams[index].1;
+
+        if headshot {
+            team.headshots.push((file_name.clone(), data));
+        } else if file_name == "teaminfo.json" {
+            team.teaminfo = Some(data);
+        } else if file_name == "roster.json" {
+            team.roster = Some(data);
+        } else if team.logo.replace((file_name.clone(), data)).is_some() {
+            // Only one logo per team
+            return None;
+        }
+    }
+
+    Some(result)
+}
+
+// Imports the teams in a zip made by one of the export routes
+async fn import_teams_handler(
+    bundle: axum::body::Bytes,
+) -> Result<Json<Vec<TeamApiInfo>>, StatusCode> {
+    let (teams, _) = import_bundle(bundle).await?;
+    Ok(Json(teams))
+}
+
+// Imports the matches in a zip made by one of the export routes, together with their teams
+async fn import_presets_handler(
+    bundle: axum::body::Bytes,
+) -> Result<Json<Vec<PresetInfo>>, StatusCode> {
+    let (_, matches) = import_bundle(bundle).await?;
+
+    let mut presets = Vec::new();
+    for saved in matches {
+        presets.push(read_preset(&saved.id).await.ok_or(StatusCode::NOT_FOUND)?);
+    }
+    Ok(Json(presets))
+}
+
+// Imports every team and match of a bundle, nothing is imported unless all of them are valid and could be written
+async fn import_bundle(
+    bundle: axum::body::Bytes,
+) -> Result<(Vec<TeamApiInfo>, Vec<SavedMatch>), StatusCode> {
+    let max_file_size = *MAX_UPLOAD_MB.lock().await * 1024 * 1024;
+    let limits = BundleLimits {
+        max_file_size,
+        max_total_size: max_file_size * BUNDLE_MAX_TOTAL_FACTOR,
+        max_entries: BUNDLE_MAX_ENTRIES,
+    };
+
+    let bundle = tokio::task::spawn_blocking(move || read_bundle(&bundle, &limits))
+        .await
+        .unwrap()
+        .ok_or_else(|| {
+            warn!("FAIL: import is not a valid team bundle");
+            StatusCode::BAD_REQUEST
+        })?;
+
+    // Matches refer to the team folders of the bundle
+    let bundle_matches: Vec<SavedMatch> = match &bundle.matches {
+        Some(json) => serde_json::from_slice(json).map_err(|_| {
+            warn!("FAIL: import has an invalid {}", BUNDLE_MATCHES_FILE);
+            StatusCode::BAD_REQUEST
+        })?,
+        None => Vec::new(),
+    };
+    for saved in &bundle_matches {
+        for dir in [&saved.home, &saved.away] {
+            if !bundle.teams.iter().any(|(d, _)| d == dir) {
+                warn!("FAIL: import match {} has no team {}", saved.id, dir);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+
+    let mut valid_teams = Vec::new();
+
+    for (dir, team) in bundle.teams {
+        let mut info: TeamInfoContainer = team
+            .teaminfo
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| {
+                warn!("FAIL: import team {} has no valid teaminfo.json", dir);
+                StatusCode::BAD_REQUEST
+            })?;
+
+        let (Some(primary_color), Some(secondary_color)) = (
+            normalize_color(&info.primary_color),
+            normalize_color(&info.secondary_color),
+        ) else {
+            warn!("FAIL: import team {} has invalid colors", dir);
+            return Err(StatusCode::BAD_REQUEST);
+        };
+        if info.name.trim().is_empty() {
+            warn!("FAIL: import team {} has no name", dir);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        info.primary_color = primary_color;
+        info.secondary_color = secondary_color;
+
+        let logo = match team.logo {
+            Some((file_name, data)) => {
+                let logo = process_image(data)
+                    .await
+                    .filter(|(kind, _)| kind.logo_file_name() == file_name)
+                    .ok_or_else(|| {
+                        warn!("FAIL: import team {} has an invalid {}", dir, file_name);
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE
+                    })?;
+                Some(logo)
+            }
+            None => None,
+        };
+
+        let roster: Vec<Player> = match team.roster {
+            Some(json) => serde_json::from_slice(&json).map_err(|_| {
+                warn!("FAIL: import team {} has an invalid roster.json", dir);
+                StatusCode::BAD_REQUEST
+            })?,
+            None => Vec::new(),
+        };
+        if let Err(err) = validate_roster(&roster) {
+            warn!("FAIL: import team {} roster: {}", dir, err);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        // Headshots are named after the jersey number of a player on the roster
+        let mut headshots = Vec::new();
+        for (file_name, data) in team.headshots {
+            let headshot = match file_name.split_once('.') {
+                Some((number, extension)) if roster.iter().any(|p| p.number == number) => {
+                    process_image(data)
+                        .await
+                        .filter(|(kind, _)| kind.file_extension() == extension)
+                        .map(|headshot| (number.to_string(), headshot))
+                }
+                _ => None,
+            };
+            headshots.push(headshot.ok_or_else(|| {
+                warn!(
+                    "FAIL: import team {} has an invalid headshot {}",
+                    dir, file_name
+                );
+                StatusCode::BAD_REQUEST
+            })?);
+        }
+
+        valid_teams.push((dir, info, logo, roster, headshots));
+    }
+
+    // Every team is written to a hidden folder first, they are only moved into the library once all of them are written
+    let mut staged: Vec<(String, String, TeamInfoContainer)> = Vec::new();
+    let mut staged_ok = Ok(());
+
+    for (dir, info, logo, roster, headshots) in valid_teams {
+        // Bundle ids are kept unless they are taken or weren't made by generate_team_id
+        let taken = |id: &str| {
+            Path::new(&format!("./teams/{}", id)).exists()
+                || staged.iter().any(|(_, staged_id, _)| staged_id == id)
+        };
+        let id = if valid_team_id(&dir) && !taken(&dir) {
+            dir.clone()
+        } else {
+            loop {
+                let id = unused_team_id();
+                if !taken(&id) {
+                    break id;
+                }
+            }
+        };
+        let tmp_dir = format!("./teams/.tmp-{}", id);
+        staged.push((dir, id, info.clone()));
+
+        staged_ok = match create_team_dir(&tmp_dir, &info, logo).await {
+            Ok(()) => write_bundle_players(&tmp_dir, &roster, headshots).await,
+            Err(err) => Err(err),
+        };
+        if staged_ok.is_err() {
+            break;
+        }
+    }
+
+    let mut moved: Vec<String> = Vec::new();
+    if staged_ok.is_ok() {
+        for (_, id, _) in &staged {
+            staged_ok =
+                tokio::fs::rename(format!("./teams/.tmp-{}", id), format!("./teams/{}", id)).await;
+            if staged_ok.is_err() {
+                break;
+            }
+            moved.push(id.clone());
+        }
+    }
+
+    // Matches get new ids if theirs are taken, and point at the ids their teams were imported as
+    let mut matches = MATCHES.lock().await;
+    let mut imported_matches = Vec::new();
+    if staged_ok.is_ok() {
+        let team_id = |dir: &str| {
+            staged
+                .iter()
+                .find(|(d, _, _)| d == dir)
+                .map(|(_, id, _)| id.clone())
+                .unwrap()
+        };
+        for saved in bundle_matches {
+            let taken = |id: &str| {
+                matches.iter().any(|m| m.id == id)
+                    || imported_matches.iter().any(|m: &SavedMatch| m.id == id)
+            };
+            let id = if valid_team_id(&saved.id) && !taken(&saved.id) {
+                saved.id
+            } else {
+                loop {
+                    let id = unused_match_id(&matches);
+                    if !taken(&id) {
+                        break id;
+                    }
+                }
+            };
+            imported_matches.push(SavedMatch {
+                id,
+                home: team_id(&saved.home),
+                away: team_id(&saved.away),
+            });
+        }
+
+        if !imported_matches.is_empty() {
+            let mut all_matches = matches.clone();
+            all_matches.extend(imported_matches.iter().cloned());
+            staged_ok = save_matches(&all_matches).await;
+            if staged_ok.is_ok() {
+                *matches = all_matches;
+            }
+        }
+    }
+    drop(matches);
+
+    // Rolls back everything this import wrote
+    if let Err(err) = staged_ok {
+        warn!(
+            "FAIL: could not import the bundle, nothing was imported: {}",
+            err
+        );
+        for (_, id, _) in &staged {
+            let _ = tokio::fs::remove_dir_all(format!("./teams/.tmp-{}", id)).await;
+        }
+        for id in &moved {
+            let _ = tokio::fs::remove_dir_all(format!("./teams/{}", id)).await;
+        }
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let mut imported = Vec::new();
+    for (_, id, info) in staged {
+        info!("IMPORT: team {} as {}", info.name, id);
+        imported.push(TeamApiInfo::new(id, info));
+    }
+    for saved in &imported_matches {
+        info!("IMPORT: match {}", saved.id);
+    }
+
+    Ok((imported, imported_matches))
+}
+
+// Writes the roster and headshots of an imported team into its folder
+async fn write_bundle_players(
+    dir: &str,
+    roster: &[Player],
+    headshots: Vec<(String, (ImageKind, Vec<u8>))>,
+) -> std::io::Result<()> {
+    if !roster.is_empty() {
+        let json = serde_json::to_string(roster).expect("Failed to serialize roster");
+        tokio::fs::write(format!("{}/roster.json", dir), json).await?;
+    }
+
+    if !headshots.is_empty() {
+        tokio::fs::create_dir_all(format!("{}/headshots", dir)).await?;
+    }
+    for (number, (kind, headshot)) in headshots {
+        tokio::fs::write(
+            format!("{}/headshots/{}.{}", dir, number, kind.file_extension()),
+            headshot,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// endregion: --- Team bundles
+// region: --- Rosters
+
+// A player on a team's roster, players are identified by their jersey number
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Player {
+    number: String,
+    name: String,
+    #[serde(default)]
+    position: String,
+}
+
+impl Player {
+    // How a player is shown on the overlay and in logs
+    fn label(&self) -> String {
+        format!("#{} {}", self.number, self.name)
+    }
+}
+
+// A player as returned by the roster API, the headshot is linked if there is one
+#[derive(Serialize)]
+struct PlayerApiInfo {
+    #[serde(flatten)]
+    player: Player,
+    headshot: Option<String>,
+}
+
+// Fields of a player that the single player route may set, the number comes from the path
+#[derive(Deserialize)]
+struct PlayerFields {
+    name: String,
+    #[serde(default)]
+    position: String,
+}
+
+// The player picked on the dashboard when recording a score or a foul
+#[derive(Deserialize)]
+struct PlayerRef {
+    player: Option<String>,
+}
+
+// Jersey numbers are one to three digits, they are also used as file names for headshots
+fn valid_jersey_number(number: &str) -> bool {
+    (1..=3).contains(&number.len()) && number.chars().all(|c| c.is_ascii_digit())
+}
+
+// Checks that every player has a valid, unique number and a name
+fn validate_roster(roster: &[Player]) -> Result<(), String> {
+    let mut numbers: Vec<&str> = Vec::new();
+
+    for player in roster {
+        if !valid_jersey_number(&player.number) {
+            return Err(format!("invalid jersey number {:?}", player.number));
+        }
+        if player.name.trim().is_empty() {
+            return Err(format!("#{} has no name", player.number));
+        }
+        if numbers.contains(&player.number.as_str()) {
+            return Err(format!("#{} is on the roster twice", player.number));
+        }
+        numbers.push(&player.number);
+    }
+
+    Ok(())
+}
+
+// Reads the roster of a team, a team without a roster file has no players
+async fn read_roster(id: &str) -> Vec<Player> {
+    match tokio::fs::read_to_string(format!("./teams/{}/roster.json", id)).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Writes the roster of a team and removes the headshots of players that aren't on it anymore
+async fn write_roster(id: &str, roster: &[Player]) -> Result<(), StatusCode> {
+    let json = serde_json::to_string(roster).expect("Failed to serialize roster");
+    if let Err(err) = tokio::fs::write(format!("./teams/{}/roster.json", id), json).await {
+        warn!("FAIL: could not save the roster of {}: {}", id, err);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    if let Ok(mut headshots) = tokio::fs::read_dir(format!("./teams/{}/headshots", id)).await {
+        while let Ok(Some(entry)) = headshots.next_entry().await {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let number = file_name.split('.').next().unwrap_or_default();
+            if !roster.iter().any(|p| p.number == number) {
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Reads the headshot of a player, whichever format it was stored in
+async fn read_headshot(id: &str, number: &str) -> Option<Vec<u8>> {
+    for extension in ["png", "svg"] {
+        if let Ok(headshot) =
+            tokio::fs::read(format!("./teams/{}/headshots/{}.{}", id, number, extension)).await
+        {
+            return Some(headshot);
+        }
+    }
+    None
+}
+
+async fn player_api_info(id: &str, player: Player) -> PlayerApiInfo {
+    let headshot = match read_headshot(id, &player.number).await {
+        Some(_) => Some(format!(
+            "/api/teams/{}/roster/{}/headshot",
+            id, player.number
+        )),
+        None => None,
+    };
+
+    PlayerApiInfo { player, headshot }
+}
+
+// Parses a roster from csv with the columns number, name and an optional position, a header row is skipped
+fn parse_roster_csv(csv_data: &[u8]) -> Result<Vec<Player>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(csv_data);
+    let mut roster = Vec::new();
+
+    for (line, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| err.to_string())?;
+        let number = record.get(0).unwrap_or_default();
+
+        if line == 0 && !valid_jersey_number(number) {
+            continue;
+        }
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+
+        roster.push(Player {
+            number: number.to_string(),
+            name: record.get(1).unwrap_or_default().to_string(),
+            position: record.get(2).unwrap_or_default().to_string(),
+        });
+    }
+
+    validate_roster(&roster).map(|_| roster)
+}
+
+// Finds a player on the roster of the team loaded as home or away
+async fn loaded_player(side: &str, number: &str) -> Option<Player> {
+    let team_id = match side {
+        "home" => HOME_TEAM_ID.lock().await.clone(),
+        _ => AWAY_TEAM_ID.lock().await.clone(),
+    }?;
+
+    read_roster(&team_id)
+        .await
+        .into_iter()
+        .find(|p| p.number == number)
+}
+
+// Resolves the player picked on the dashboard, Ok(None) if no player was picked
+async fn picked_player(
+    side: &str,
+    player: Option<Form<PlayerRef>>,
+) -> Result<Option<Player>, StatusCode> {
+    let Some(number) = player
+        .and_then(|Form(p)| p.player)
+        .filter(|n| !n.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    match loaded_player(side, &number).await {
+        Some(player) => Ok(Some(player)),
+        None => {
+            warn!("FAIL: #{} is not on the {} roster", number, side);
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+async fn get_roster_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<PlayerApiInfo>>, StatusCode> {
+    read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut players = Vec::new();
+    for player in read_roster(&id).await {
+        players.push(player_api_info(&id, player).await);
+    }
+
+    Ok(Json(players))
+}
+
+// Replaces the whole roster of a team
+async fn put_roster_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(roster): Json<Vec<Player>>,
+) -> StatusCode {
+    if read_team(&id).await.is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+    if let Err(err) = validate_roster(&roster) {
+        warn!("FAIL: roster of {}: {}", id, err);
+        return StatusCode::BAD_REQUEST;
+    }
+
+    info!("ROSTER: {} players for {}", roster.len(), id);
+    match write_roster(&id, &roster).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+// Replaces the roster of a team with the csv in the request body
+async fn import_roster_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    csv_data: axum::body::Bytes,
+) -> StatusCode {
+    if read_team(&id).await.is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+
+    match parse_roster_csv(&csv_data) {
+        Ok(roster) => {
+            info!("ROSTER: imported {} players for {}", roster.len(), id);
+            match write_roster(&id, &roster).await {
+                Ok(()) => StatusCode::NO_CONTENT,
+                Err(status) => status,
+            }
+        }
+        Err(err) => {
+            warn!("FAIL: roster csv for {}: {}", id, err);
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+// Adds a player to a roster or changes the one with the same number
+async fn put_player_handler(
+    axum::extract::Path((id, number)): axum::extract::Path<(String, String)>,
+    Json(fields): Json<PlayerFields>,
+) -> Result<Json<PlayerApiInfo>, StatusCode> {
+    read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    let player = Player {
+        number,
+        name: fields.name,
+        position: fields.position,
+    };
+    validate_roster(std::slice::from_ref(&player)).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut roster = read_roster(&id).await;
+    match roster.iter_mut().find(|p| p.number == player.number) {
+        Some(existing) => *existing = player.clone(),
+        None => roster.push(player.clone()),
+    }
+
+    info!("ROSTER: update {} of {}", player.label(), id);
+    write_roster(&id, &roster).await?;
+    Ok(Json(player_api_info(&id, player).await))
+}
+
+async fn delete_player_handler(
+    axum::extract::Path((id, number)): axum::extract::Path<(String, String)>,
+) -> StatusCode {
+    if read_team(&id).await.is_none() {
+        return StatusCode::NOT_FOUND;
+    }
+
+    let mut roster = read_roster(&id).await;
+    let players = roster.len();
+    roster.retain(|p| p.number != number);
+    if roster.len() == players {
+        return StatusCode::NOT_FOUND;
+    }
+
+    info!("ROSTER: remove #{} from {}", number, id);
+    match write_roster(&id, &roster).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(status) => status,
+    }
+}
+
+async fn headshot_handler(
+    axum::extract::Path((id, number)): axum::extract::Path<(String, String)>,
+) -> Result<Response<Body>, StatusCode> {
+    read_team(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+    if !valid_jersey_number(&number) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let headshot = read_headshot(&id, &number)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(image_response(headshot))
+}
+
+// Replaces the headshot of a player with the image in the request body
+async fn replace_headshot_handler(
+    axum::extract::Path((id, number)): axum::extract::Path<(String, String)>,
+    headshot: axum::body::Bytes,
+) -> StatusCode {
+    if read_team(&id).await.is_none() || !read_roster(&id).await.iter().any(|p| p.number == number)
+    {
+        return StatusCode::NOT_FOUND;
+    }
+    let Some((kind, headshot)) = process_image(headshot.to_vec()).await else {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    };
+
+    let dir = format!("./teams/{}/headshots", id);
+    if let Err(err) = tokio::fs::create_dir_all(&dir).await {
+        warn!("FAIL: could not create {}: {}", dir, err);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    for extension in ["png", "svg"] {
+        let _ = tokio::fs::remove_file(format!("{}/{}.{}", dir, number, extension)).await;
+    }
+
+    info!(
+        "HEADSHOT: replace #{} of {}, LENGTH: {}",
+        number,
+        id,
+        headshot.len()
+    );
+    let file = format!("{}/{}.{}", dir, number, kind.file_extension());
+    match tokio::fs::write(&file, headshot).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(err) => {
+            warn!("FAIL: could not write {}: {}", file, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+// Handles and returns the roster of a loaded team as options for the player pickers on the dashboard
+async fn roster_options_handler(
+    axum::extract::Path(side): axum::extract::Path<String>,
+    picker: Option<Form<HashMap<String, String>>>,
+) -> Html<String> {
+    let team_id = match side.as_str() {
+        "home" => HOME_TEAM_ID.lock().await.clone(),
+        _ => AWAY_TEAM_ID.lock().await.clone(),
+    };
+    // The picker sends its own value so the selection survives a refresh
+    let selected = picker
+        .and_then(|Form(p)| p.into_values().next())
+        .unwrap_or_default();
+
+    let mut options = String::from("<option value=\"\">No player</option>");
+    if let Some(team_id) = team_id {
+        for player in read_roster(&team_id).await {
+            options += &format!(
+                "<option value=\"{}\"{}>{}</option>",
+                player.number,
+                if player.number == selected {
+                    " selected"
+                } else {
+                    ""
+                },
+                html_escape(&player.label()),
+            );
+        }
+    }
+
+    Html(options)
+}
+
+// endregion: --- Rosters
+// region: --- Images
+
+// Image formats accepted for logos and sponsors, detected from the magic bytes instead of the file name
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImageKind {
+    Png,
+    Jpeg,
+    WebP,
+    Svg,
+}
+
+impl ImageKind {
+    fn detect(bytes: &[u8]) -> Option<ImageKind> {
+        if bytes.starts_with(PNG_SIGNATURE) {
+            Some(ImageKind::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageKind::Jpeg)
+        } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(ImageKind::WebP)
+        } else if is_svg(bytes) {
+            Some(ImageKind::Svg)
+        } else {
+            None
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ImageKind::Png => "image/png",
+            ImageKind::Jpeg => "image/jpeg",
+            ImageKind::WebP => "image/webp",
+            ImageKind::Svg => "image/svg+xml",
+        }
+    }
+
+    // Raster images are stored as png after normalizing, svg is kept as it is
+    fn file_extension(self) -> &'static str {
+        match self {
+            ImageKind::Svg => "svg",
+            _ => "png",
+        }
+    }
+
+    fn logo_file_name(self) -> &'static str {
+        match self {
+            ImageKind::Svg => "logo.svg",
+            _ => "logo.png",
+        }
+    }
+}
+
+// SVG has no magic bytes, so it has to be text that starts with markup and contains an svg element
+fn is_svg(bytes: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(bytes) else {
+        return false;
+    };
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let head: String = text.chars().take(1024).collect();
+
+    text.starts_with('<') && head.contains("<svg")
+}
+
+// Converts jpeg and webp to png and downscales raster images larger than max_size, None if it isn't a supported image
+fn normalize_image(bytes: Vec<u8>, max_size: u32) -> Option<(ImageKind, Vec<u8>)> {
+    let kind = ImageKind::detect(&bytes)?;
+    let format = match kind {
+        ImageKind::Svg => return Some((kind, bytes)),
+        ImageKind::Png => image::ImageFormat::Png,
+        ImageKind::Jpeg => image::ImageFormat::Jpeg,
+        ImageKind::WebP => image::ImageFormat::WebP,
+    };
+
+    let img = image::load_from_memory_with_format(&bytes, format).ok()?;
+    let oversized = img.width() > max_size || img.height() > max_size;
+
+    if kind == ImageKind::Png && !oversized {
+        return Some((kind, bytes));
+    }
+
+    let img = if oversized {
+        img.resize(max_size, max_size, image::imageops::FilterType::Lanczos3)
+    } else {
+        img
+    };
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut png, image::ImageFormat::Png).ok()?;
+
+    Some((ImageKind::Png, png.into_inner()))
+}
+
+// Normalizes an uploaded image on the blocking pool, decoding and resizing can take a while
+async fn process_image(bytes: Vec<u8>) -> Option<(ImageKind, Vec<u8>)> {
+    let max_size = *LOGO_MAX_SIZE.lock().await;
+    tokio::task::spawn_blocking(move || normalize_image(bytes, max_size))
+        .await
+        .unwrap()
+}
+
+// Images served under /img/:hash. Every team, sponsor, lower third and loaded logo owns the image it shows,
+// an image is dropped once no owner uses it anymore
+#[derive(Default)]
+struct ImageCache {
+    images: HashMap<String, Arc<Vec<u8>>>,
+    owners: HashMap<String, String>,
+}
+
+impl ImageCache {
+    // Stores the image of an owner and returns its hash, the image the owner had before is dropped if it is unused now
+    fn insert(&mut self, owner: String, bytes: Vec<u8>) -> String {
+        let hash: String = Sha256::digest(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        self.images
+            .entry(hash.clone())
+            .or_insert_with(|| Arc::new(bytes));
+        if let Some(old) = self.owners.insert(owner, hash.clone()) {
+            self.drop_unused(&old);
+        }
+
+        hash
+    }
+
+    fn release(&mut self, owner: &str) {
+        if let Some(old) = self.owners.remove(owner) {
+            self.drop_unused(&old);
+        }
+    }
+
+    // Releases every owner that isn't kept
+    fn retain_owners(&mut self, keep: impl Fn(&str) -> bool) {
+        let released: Vec<String> = self
+            .owners
+            .keys()
+            .filter(|owner| !keep(owner))
+            .cloned()
+            .collect();
+        for owner in released {
+            self.release(&owner);
+        }
+    }
+
+    fn drop_unused(&mut self, hash: &str) {
+        if !self.owners.values().any(|h| h == hash) {
+            self.images.remove(hash);
+        }
+    }
+
+    fn hash(&self, owner: &str) -> Option<&String> {
+        self.owners.get(owner)
+    }
+
+    fn get(&self, hash: &str) -> Option<Arc<Vec<u8>>> {
+        self.images.get(hash).cloned()
+    }
+}
+
+// Stores the image of an owner in the image cache and returns its url, the url changes whenever the contents do
+async fn cache_image(owner: String, bytes: Vec<u8>) -> String {
+    format!("/img/{}", IMAGE_CACHE.lock().await.insert(owner, bytes))
+}
+
+async fn release_image(owner: &str) {
+    IMAGE_CACHE.lock().await.release(owner);
+}
+
+// Returns the url of the image an owner has in the image cache
+async fn image_url(owner: &str) -> Option<String> {
+    IMAGE_CACHE
+        .lock()
+        .await
+        .hash(owner)
+        .map(|hash| format!("/img/{}", hash))
+}
+
+// Handles and returns cached images, browsers can keep them forever since the url is the hash of the contents
+async fn cached_image_handler(
+    axum::extract::Path(hash): axum::extract::Path<String>,
+    headers: HeaderMap,
+) -> Response<Body> {
+    cached_image_response(&hash, &headers, "public, max-age=31536000, immutable").await
+}
+
+// Answers with an image of the cache, or 304 if the browser already has it
+async fn cached_image_response(
+    hash: &str,
+    headers: &HeaderMap,
+    cache_control: &str,
+) -> Response<Body> {
+    let etag = format!("\"{}\"", hash);
+
+    let Some(bytes) = IMAGE_CACHE.lock().await.get(hash) else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap();
+    };
+
+    let cached = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        });
+
+    let mut response = if cached {
+        Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap()
+    } else {
+        image_response(bytes.to_vec())
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(ETAG, etag.parse().unwrap());
+    response_headers.insert(CACHE_CONTROL, cache_control.parse().unwrap());
+
+    response
+}
+
+// Serves an image with the content type of its format, scripts inside svg files are not allowed to run
+fn image_response(bytes: Vec<u8>) -> Response<Body> {
+    let kind = ImageKind::detect(&bytes).unwrap_or(ImageKind::Png);
+    let mut response = Response::builder().header(CONTENT_TYPE, kind.content_type());
+
+    if kind == ImageKind::Svg {
+        response = response.header("Content-Security-Policy", "script-src 'none'");
+    }
+
+    response.body(Body::from(bytes)).unwrap()
+}
+
+// Reads the logo of a team in the library, whichever format it was stored in
+async fn read_team_logo(id: &str) -> Option<Vec<u8>> {
+    read_logo_in(&format!("./teams/{}", id)).await
+}
+
+// Reads the logo of a team folder, whichever format it was saved in
+async fn read_logo_in(dir: &str) -> Option<Vec<u8>> {
+    for file_name in ["logo.png", "logo.svg"] {
+        if let Ok(logo) = tokio::fs::read(format!("{}/{}", dir, file_name)).await {
+            return Some(logo);
+        }
+    }
+    None
+}
+
+// Writes a normalized logo into a team folder and removes the logo of the other format
+async fn write_team_logo(dir: &str, kind: ImageKind, logo: &[u8]) -> Result<(), StatusCode> {
+    for file_name in ["logo.png", "logo.svg"] {
+        if file_name != kind.logo_file_name() {
+            let _ = tokio::fs::remove_file(format!("{}/{}", dir, file_name)).await;
+        }
+    }
+
+    tokio::fs::write(format!("{}/{}", dir, kind.logo_file_name()), logo)
+        .await
+        .map_err(|err| {
+            warn!("FAIL: could not write the logo in {}: {}", dir, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// endregion: --- Images
+// region: --- Sponsor roll
+
+// An entry of the sponsor manifest, its order is the order of the sponsor roll
+#[derive(Serialize, Deserialize, Clone)]
+struct SponsorEntry {
+    file: String,
+    #[serde(default = "sponsor_enabled_default")]
+    enabled: bool,
+    // Seconds the sponsor stays on screen each time it is shown
+    #[serde(default = "sponsor_duration_default")]
+    duration_secs: u64,
+    // A sponsor with weight 2 is shown twice as often as one with weight 1
+    #[serde(default = "sponsor_weight_default")]
+    weight: u32,
+    // The sponsor is only shown between these local times, either can be left out
+    #[serde(default)]
+    active_from: Option<String>,
+    #[serde(default)]
+    active_until: Option<String>,
+    // Only shown while the clock is stopped at the end of a period or a timeout is up
+    #[serde(default)]
+    breaks_only: bool,
+}
+
+fn sponsor_enabled_default() -> bool {
+    true
+}
+
+fn sponsor_duration_default() -> u64 {
+    5
+}
+
+fn sponsor_weight_default() -> u32 {
+    1
+}
+
+impl SponsorEntry {
+    fn new(file: String) -> SponsorEntry {
+        SponsorEntry {
+            file,
+            enabled: true,
+            duration_secs: sponsor_duration_default(),
+            weight: sponsor_weight_default(),
+            active_from: None,
+            active_until: None,
+            breaks_only: false,
+        }
+    }
+
+    // Brings a duration or weight edited by hand back into the range of the settings form, returns whether it did
+    fn clamp_settings(&mut self) -> bool {
+        let duration_secs = self.duration_secs.clamp(1, SPONSOR_MAX_DURATION_SECS);
+        let weight = self.weight.clamp(1, SPONSOR_MAX_WEIGHT);
+        let changed = duration_secs != self.duration_secs || weight != self.weight;
+
+        self.duration_secs = duration_secs;
+        self.weight = weight;
+        changed
+    }
+
+    // Whether the sponsor may be shown right now
+    fn eligible(&self, now: chrono::NaiveDateTime, in_break: bool) -> bool {
+        let after = |time: &Option<String>| {
+            time.as_deref()
+                .and_then(parse_local_datetime)
+                .is_none_or(|time| now >= time)
+        };
+        let before = |time: &Option<String>| {
+            time.as_deref()
+                .and_then(parse_local_datetime)
+                .is_none_or(|time| now < time)
+        };
+
+        self.enabled
+            && after(&self.active_from)
+            && before(&self.active_until)
+            && (!self.breaks_only || in_break)
+    }
+}
+
+// Parses a local date and time as sent by datetime inputs, with or without seconds
+fn parse_local_datetime(time: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(time, SPONSOR_WINDOW_FORMAT)
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+// Time a sponsor was on air, counted while the sponsor roll is shown
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct SponsorImpressions {
+    seconds: u64,
+    shows: u64,
+}
+
+// A sponsor image that was loaded from ./sponsors
+#[derive(Serialize, Clone)]
+struct Sponsor {
+    #[serde(flatten)]
+    entry: SponsorEntry,
+    url: String,
+    // Size and modification time of the file, unchanged files aren't processed again on reload
+    #[serde(skip)]
+    file_state: (u64, Option<SystemTime>),
+}
+
+// Sponsor files are stored as they were uploaded, the name can't leave the sponsors folder
+fn valid_sponsor_file(file: &str) -> bool {
+    !file.is_empty()
+        && file.len() <= 128
+        && !file.starts_with('.')
+        && file != SPONSOR_MANIFEST_NAME
+        && file
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+// Makes a file name for an uploaded sponsor, characters that aren't allowed become underscores
+fn sponsor_file_name(upload_name: &str) -> String {
+    let name: String = upload_name
+        .trim_start_matches('.')
+        .chars()
+        .take(100)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.is_empty() || name == SPONSOR_MANIFEST_NAME {
+        String::from("sponsor")
+    } else {
+        name
+    }
+}
+
+async fn read_sponsor_manifest() -> Vec<SponsorEntry> {
+    match tokio::fs::read_to_string(format!("./sponsors/{}", SPONSOR_MANIFEST_NAME)).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+            warn!(
+                "SPONSORS: ignoring invalid {}: {}",
+                SPONSOR_MANIFEST_NAME, err
+            );
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+async fn write_sponsor_manifest(entries: &[SponsorEntry]) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(entries).expect("Failed to serialize sponsors");
+    tokio::fs::write(format!("./sponsors/{}", SPONSOR_MANIFEST_NAME), json).await
+}
+
+// Name, size and modification time of every file in ./sponsors
+type SponsorDirState = Vec<(String, u64, Option<SystemTime>)>;
+
+// Lists the files in ./sponsors, the watcher reloads the sponsors when this changes
+async fn sponsor_dir_state() -> SponsorDirState {
+    let mut entries = tokio::fs::read_dir("./sponsors").await.unwrap();
+    let mut state = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Ok(metadata) = entry.metadata().await {
+            state.push((
+                entry.file_name().to_string_lossy().to_string(),
+                metadata.len(),
+                metadata.modified().ok(),
+            ));
+        }
+    }
+
+    state.sort();
+    state
+}
+
+// Loads every sponsor image in the order of the manifest, new files are added to the end of it
+async fn load_sponsors(dir_state: &SponsorDirState) -> Vec<Sponsor> {
+    let manifest = read_sponsor_manifest().await;
+    let previous = SPONSORS.lock().await.clone();
+
+    let mut files: Vec<String> = dir_state
+        .iter()
+        .map(|(file, _, _)| file.clone())
+        .filter(|file| valid_sponsor_file(file))
+        .collect();
+    files.retain(|file| Path::new(&format!("./sponsors/{}", file)).is_file());
+
+    let mut entries: Vec<SponsorEntry> = manifest
+        .iter()
+        .filter(|entry| files.contains(&entry.file))
+        .cloned()
+        .collect();
+    for file in files {
+        if !entries.iter().any(|entry| entry.file == file) {
+            entries.push(SponsorEntry::new(file));
+        }
+    }
+
+    // Entries of files that were removed are dropped, a file that can't be loaded keeps its place
+    let listed: Vec<&str> = manifest.iter().map(|e| e.file.as_str()).collect();
+    if entries
+        .iter()
+        .map(|e| e.file.as_str())
+        .collect::<Vec<&str>>()
+        != listed
+    {
+        if let Err(err) = write_sponsor_manifest(&entries).await {
+            warn!(
+                "SPONSORS: could not update {}: {}",
+                SPONSOR_MANIFEST_NAME, err
+            );
+        }
+    }
+
+    let mut sponsors = Vec::new();
+    for mut entry in entries {
+        if entry.clamp_settings() {
+            warn!(
+                "SPONSORS: {} has a duration or weight out of range, using {}s and weight {}",
+                entry.file, entry.duration_secs, entry.weight
+            );
+        }
+
+        let file_state = dir_state
+            .iter()
+            .find(|(file, _, _)| *file == entry.file)
+            .map(|(_, len, modified)| (*len, *modified))
+            .unwrap_or_default();
+
+        if let Some(sponsor) = previous
+            .iter()
+            .find(|s| s.entry.file == entry.file && s.file_state == file_state)
+        {
+            sponsors.push(Sponsor {
+                entry,
+                url: sponsor.url.clone(),
+                file_state,
+            });
+            continue;
+        }
+
+        let img_bytes = tokio::fs::read(format!("./sponsors/{}", entry.file))
+            .await
+            .unwrap_or_default();
+        let Some((_, img_bytes)) = process_image(img_bytes).await else {
+            warn!(
+                "SPONSORS: skipping {}, not a png, jpeg, webp or svg image",
+                entry.file
+            );
+            continue;
+        };
+
+        let url = cache_image(format!("sponsor:{}", entry.file), img_bytes).await;
+        sponsors.push(Sponsor {
+            entry,
+            url,
+            file_state,
+        });
+    }
+
+    sponsors
+}
+
+// Reloads the sponsors from disk and rebuilds the sponsor roll from the enabled ones
+async fn reload_sponsors() {
+    // Held for the whole reload so the watcher and the sponsor page don't reload at the same time
+    let mut dir_state = SPONSOR_DIR_STATE.lock().await;
+    let new_dir_state = sponsor_dir_state().await;
+    let sponsors = load_sponsors(&new_dir_state).await;
+
+    info!(
+        "SPONSORS: loaded {} sponsors, {} enabled",
+        sponsors.len(),
+        sponsors.iter().filter(|s| s.entry.enabled).count()
+    );
+
+    // Images of sponsors that were removed or can't be loaded anymore are dropped
+    IMAGE_CACHE.lock().await.retain_owners(|owner| {
+        owner
+            .strip_prefix("sponsor:")
+            .is_none_or(|file| sponsors.iter().any(|s| s.entry.file == file))
+    });
+    *SPONSORS.lock().await = sponsors;
+
+    // A sponsor that was removed or disabled leaves the screen right away
+    SPONSOR_ROLL_WAKE.notify_one();
+
+    *dir_state = new_dir_state;
+}
+
+// Reloads the sponsors whenever a file in ./sponsors is added, removed or changed
+async fn sponsor_watcher() {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+
+        if sponsor_dir_state().await != *SPONSOR_DIR_STATE.lock().await {
+            reload_sponsors().await;
+        }
+    }
+}
+
+// The clock stopped at 0:00 is a break between periods, timeouts count as breaks too
+async fn in_period_break() -> bool {
+    let clock_ended =
+        !*TIME_STARTED.lock().await && *TIME_MINS.lock().await == 0 && *TIME_SECS.lock().await == 0;
+
+    clock_ended || timeout_showing().await
+}
+
+// Picks the next sponsor with a smooth weighted round robin, sponsors with the same weight take turns in manifest order
+async fn next_sponsor(current_weights: &mut HashMap<String, i64>) -> Option<Sponsor> {
+    let now = chrono::Local::now().naive_local();
+    let in_break = in_period_break().await;
+    let eligible: Vec<Sponsor> = SPONSORS
+        .lock()
+        .await
+        .iter()
+        .filter(|s| s.entry.eligible(now, in_break))
+        .cloned()
+        .collect();
+
+    pick_sponsor(&eligible, current_weights).cloned()
+}
+
+// The smooth weighted round robin of next_sponsor, every eligible sponsor gains its weight and the one picked pays the total
+fn pick_sponsor<'a>(
+    eligible: &'a [Sponsor],
+    current_weights: &mut HashMap<String, i64>,
+) -> Option<&'a Sponsor> {
+    current_weights.retain(|file, _| eligible.iter().any(|s| s.entry.file == *file));
+
+    let total: i64 = eligible.iter().map(|s| s.entry.weight as i64).sum();
+    let mut picked: Option<(&Sponsor, i64)> = None;
+    for sponsor in eligible {
+        let weight = current_weights
+            .entry(sponsor.entry.file.clone())
+            .or_insert(0);
+        *weight += sponsor.entry.weight as i64;
+
+        if picked.is_none_or(|(_, best)| *weight > best) {
+            picked = Some((sponsor, *weight));
+        }
+    }
+
+    let (sponsor, _) = picked?;
+    *current_weights.get_mut(&sponsor.entry.file).unwrap() -= total;
+    Some(sponsor)
+}
+
+// Whether the sponsor on screen can stay there, its settings may have changed since it was picked
+async fn sponsor_slot_valid(file: &str) -> bool {
+    if !*SHOW_SPONSOR.lock().await {
+        return false;
+    }
+
+    let now = chrono::Local::now().naive_local();
+    let in_break = in_period_break().await;
+    SPONSORS
+        .lock()
+        .await
+        .iter()
+        .any(|s| s.entry.file == file && s.entry.eligible(now, in_break))
+}
+
+// Rotates the sponsor roll, it sleeps while the roll is hidden and is woken up by SPONSOR_ROLL_WAKE
+async fn sponsor_roll_ticker() {
+    let mut current_weights: HashMap<String, i64> = HashMap::new();
+
+    loop {
+        if !*SHOW_SPONSOR.lock().await {
+            SPONSOR_ROLL_WAKE.notified().await;
+            continue;
+        }
+
+        let Some(sponsor) = next_sponsor(&mut current_weights).await else {
+            CURRENT_SPONSOR.send_replace(None);
+
+            // A schedule or a break can make a sponsor eligible without anything waking the ticker
+            tokio::select! {
+                _ = SPONSOR_ROLL_WAKE.notified() => {}
+                _ = sleep(Duration::from_secs(1)) => {}
+            }
+            continue;
+        };
+
+        CURRENT_SPONSOR.send_replace(Some(sponsor.clone()));
+        let slot_start = Instant::now();
+        let slot = Duration::from_secs(sponsor.entry.duration_secs);
+
+        // The slot ends early when the roll is hidden or the sponsor can't be shown anymore
+        loop {
+            tokio::select! {
+                _ = SPONSOR_ROLL_WAKE.notified() => {}
+                _ = sleep(slot.saturating_sub(slot_start.elapsed())) => break,
+            }
+
+            if !sponsor_slot_valid(&sponsor.entry.file).await {
+                break;
+            }
+        }
+
+        let on_air = slot_start.elapsed().min(slot).as_secs_f64().round() as u64;
+        record_sponsor_impression(&sponsor.entry.file, on_air).await;
+    }
+}
+
+async fn record_sponsor_impression(file: &str, seconds: u64) {
+    if seconds == 0 {
+        return;
+    }
+
+    let mut impressions = SPONSOR_IMPRESSIONS.lock().await;
+    let entry = impressions.entry(file.to_string()).or_default();
+    entry.seconds += seconds;
+    entry.shows += 1;
+
+    // The roll keeps going when the impressions can't be saved, they are saved again with the next one
+    let json = serde_json::to_string_pretty(&*impressions)
+        .expect("Failed to serialize sponsor impressions");
+    if let Err(err) = tokio::fs::write(SPONSOR_IMPRESSIONS_FILE, json).await {
+        warn!(
+            "SPONSORS: could not save {}: {}",
+            SPONSOR_IMPRESSIONS_FILE, err
+        );
+    }
+}
+
+async fn load_sponsor_impressions() {
+    let Ok(json) = tokio::fs::read_to_string(SPONSOR_IMPRESSIONS_FILE).await else {
+        return;
+    };
+
+    match serde_json::from_str(&json) {
+        Ok(impressions) => *SPONSOR_IMPRESSIONS.lock().await = impressions,
+        Err(err) => warn!(
+            "SPONSORS: ignoring invalid {}: {}",
+            SPONSOR_IMPRESSIONS_FILE, err
+        ),
+    }
+}
+
+async fn sponsor_roll_handler() -> Html<String> {
+    match CURRENT_SPONSOR.borrow().as_ref() {
+        Some(sponsor) => Html(format!(
+            "<img src=\"{}\" id=\"sponsor_roll_img\"/>",
+            sponsor.url
+        )),
+        None => Html(String::new()),
+    }
+}
+
+async fn show_sponsor_roll_handler() {
+    let mut show_sponsor = SHOW_SPONSOR.lock().await;
+
+    *show_sponsor = !*show_sponsor;
+    SPONSOR_ROLL_WAKE.notify_one();
+}
+
+async fn sponsor_roll_css_handler() -> Html<&'static str> {
+    let show_sponsor = SHOW_SPONSOR.lock().await;
+
+    if *show_sponsor {
+        Html("<style> #show-sponsor { background-color: rgb(227, 45, 32); } </style>")
+    } else {
+        Html("<style> #sponsor_roll_img { display: none; } #show-sponsor { background-color: #e9981f; } </style>")
+    }
+}
+
+// endregion: --- Sponsor roll
+// region: --- Sponsor management
+
+// Handles and returns the sponsor list of the sponsor page
+async fn sponsor_list_handler() -> Html<String> {
+    let sponsors = SPONSORS.lock().await.clone();
+    let impressions = SPONSOR_IMPRESSIONS.lock().await.clone();
+    let mut html = String::new();
+
+    for (i, sponsor) in sponsors.iter().enumerate() {
+        let file = &sponsor.entry.file;
+        let on_air = impressions.get(file).cloned().unwrap_or_default();
+        html += &format!(
+            "
+            <div class=\"sponsor-entry{}\">
+                <img src=\"{}\" height=\"40px\" width=\"auto\">
+                <span>{}</span>
+                <button hx-post=\"/sponsors/{}/move/up\" hx-target=\"#sponsor-list\"{}>Up</button>
+                <button hx-post=\"/sponsors/{}/move/down\" hx-target=\"#sponsor-list\"{}>Down</button>
+                <button hx-post=\"/sponsors/{}/toggle\" hx-target=\"#sponsor-list\">{}</button>
+                <button hx-delete=\"/sponsors/{}\" hx-target=\"#sponsor-list\" hx-confirm=\"Delete {}?\">Delete</button>
+            </div>
+            <form class=\"sponsor-settings\" hx-post=\"/sponsors/{}/settings\" hx-target=\"#sponsor-list\">
+                <label>Seconds: <input type=\"number\" name=\"duration_secs\" min=\"1\" max=\"3600\" value=\"{}\"></label>
+                <label>Weight: <input type=\"number\" name=\"weight\" min=\"1\" max=\"100\" value=\"{}\"></label>
+                <label>From: <input type=\"datetime-local\" name=\"active_from\" value=\"{}\"></label>
+                <label>Until: <input type=\"datetime-local\" name=\"active_until\" value=\"{}\"></label>
+                <label><input type=\"checkbox\" name=\"breaks_only\"{}> Only during breaks</label>
+                <input type=\"submit\" value=\"Save\">
+                <span>On air {}s, shown {} times</span>
+            </form>
+            ",
+            if sponsor.entry.enabled { "" } else { " disabled" },
+            sponsor.url,
+            html_escape(file),
+            file,
+            if i == 0 { " disabled" } else { "" },
+            file,
+            if i + 1 == sponsors.len() { " disabled" } else { "" },
+            file,
+            if sponsor.entry.enabled { "Disable" } else { "Enable" },
+            file,
+            file,
+            file,
+            sponsor.entry.duration_secs,
+            sponsor.entry.weight,
+            sponsor.entry.active_from.as_deref().unwrap_or_default(),
+            sponsor.entry.active_until.as_deref().unwrap_or_default(),
+            if sponsor.entry.breaks_only { " checked" } else { "" },
+            on_air.seconds,
+            on_air.shows,
+        );
+    }
+
+    if html.is_empty() {
+        html = String::from("<p>No sponsors yet</p>");
+    }
+
+    Html(html)
+}
+
+async fn list_sponsors_handler() -> Json<Vec<Sponsor>> {
+    Json(SPONSORS.lock().await.clone())
+}
+
+// Handles and returns the time every sponsor was on air as csv, sponsors that were deleted are kept
+async fn sponsor_impressions_csv_handler() -> Response<Body> {
+    let impressions = SPONSOR_IMPRESSIONS.lock().await.clone();
+    let mut files: Vec<&String> = impressions.keys().collect();
+    files.sort();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["sponsor", "seconds", "shows"])
+        .unwrap();
+    for file in files {
+        let on_air = &impressions[file];
+        writer
+            .write_record([
+                file.as_str(),
+                &on_air.seconds.to_string(),
+                &on_air.shows.to_string(),
+            ])
+            .unwrap();
+    }
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/csv")
+        .header(
+            "Content-Disposition",
+            "attachment; filename=\"sponsor-impressions.csv\"",
+        )
+        .body(Body::from(writer.into_inner().unwrap()))
+        .unwrap()
+}
+
+async fn reset_sponsor_impressions_handler() -> Html<String> {
+    SPONSOR_IMPRESSIONS.lock().await.clear();
+    tokio::fs::remove_file(SPONSOR_IMPRESSIONS_FILE).await.ok();
+    info!("SPONSORS: impressions reset");
+
+    sponsor_list_handler().await
+}
+
+async fn reload_sponsors_handler() -> Html<String> {
+    reload_sponsors().await;
+    sponsor_list_handler().await
+}
+
+// Handles sponsor uploads, every file is checked before any of them is written
+async fn upload_sponsor_handler(mut payload: Multipart) -> Result<Html<String>, StatusCode> {
+    let mut uploads: Vec<(String, axum::body::Bytes)> = Vec::new();
+
+    loop {
+        let field = match payload.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                warn!("FAIL: sponsor upload: {}", err.body_text());
+                return Err(err.status());
+            }
+        };
+
+        if field.name() != Some("sponsor") {
+            warn!(
+                "FAIL: sponsor upload has an unexpected field {:?}",
+                field.name()
+            );
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let file_name = sponsor_file_name(field.file_name().unwrap_or_default());
+        let data = match field.bytes().await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("FAIL: sponsor upload: {}", err.body_text());
+                return Err(err.status());
+            }
+        };
+
+        if ImageKind::detect(&data).is_none() {
+            warn!("FAIL: sponsor upload {} is not an image", file_name);
+            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+
+        uploads.push((file_name, data));
+    }
+
+    if uploads.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Files with a name that is already taken get a number in front of it
+    for (file_name, data) in uploads {
+        let mut file = file_name.clone();
+        let mut n = 1;
+        while Path::new(&format!("./sponsors/{}", file)).exists() {
+            file = format!("{}-{}", n, file_name);
+            n += 1;
+        }
+
+        if let Err(err) = tokio::fs::write(format!("./sponsors/{}", file), data).await {
+            warn!("FAIL: could not write sponsor {}: {}", file, err);
+            reload_sponsors().await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        info!("SPONSORS: uploaded {}", file);
+    }
+
+    reload_sponsors().await;
+    Ok(sponsor_list_handler().await)
+}
+
+async fn delete_sponsor_handler(
+    axum::extract::Path(file): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    if !valid_sponsor_file(&file) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    tokio::fs::remove_file(format!("./sponsors/{}", file))
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    info!("SPONSORS: deleted {}", file);
+
+    reload_sponsors().await;
+    Ok(sponsor_list_handler().await)
+}
+
+// Changes the manifest entry of a sponsor and reloads the sponsor roll
+async fn update_sponsor_entry(
+    file: &str,
+    update: impl FnOnce(&mut Vec<SponsorEntry>, usize),
+) -> Result<Html<String>, StatusCode> {
+    // Held until the manifest is written so a reload or another change can't write it in between
+    let dir_state = SPONSOR_DIR_STATE.lock().await;
+    let mut entries = read_sponsor_manifest().await;
+    let index = entries
+        .iter()
+        .position(|e| e.file == file)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    update(&mut entries, index);
+    if let Err(err) = write_sponsor_manifest(&entries).await {
+        warn!("FAIL: could not save {}: {}", SPONSOR_MANIFEST_NAME, err);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    drop(dir_state);
+
+    reload_sponsors().await;
+    Ok(sponsor_list_handler().await)
+}
+
+async fn toggle_sponsor_handler(
+    axum::extract::Path(file): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    update_sponsor_entry(&file, |entries, i| {
+        entries[i].enabled = !entries[i].enabled;
+        info!(
+            "SPONSORS: {} {}",
+            if entries[i].enabled {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            entries[i].file
+        );
+    })
+    .await
+}
+
+// Form of the sponsor settings, empty times leave that end of the active window open
+#[derive(Deserialize)]
+struct SponsorSettingsForm {
+    duration_secs: u64,
+    weight: u32,
+    #[serde(default)]
+    active_from: String,
+    #[serde(default)]
+    active_until: String,
+    breaks_only: Option<String>,
+}
+
+async fn sponsor_settings_handler(
+    axum::extract::Path(file): axum::extract::Path<String>,
+    Form(settings): Form<SponsorSettingsForm>,
+) -> Result<Html<String>, StatusCode> {
+    let window_time = |time: &str| -> Result<Option<String>, StatusCode> {
+        if time.trim().is_empty() {
+            return Ok(None);
+        }
+        parse_local_datetime(time.trim())
+            .map(|time| Some(time.format(SPONSOR_WINDOW_FORMAT).to_string()))
+            .ok_or(StatusCode::BAD_REQUEST)
+    };
+    let active_from = window_time(&settings.active_from)?;
+    let active_until = window_time(&settings.active_until)?;
+
+    if !(1..=SPONSOR_MAX_DURATION_SECS).contains(&settings.duration_secs)
+        || !(1..=SPONSOR_MAX_WEIGHT).contains(&settings.weight)
+    {
+        warn!("FAIL: invalid sponsor settings for {}", file);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    update_sponsor_entry(&file, |entries, i| {
+        entries[i].duration_secs = settings.duration_secs;
+        entries[i].weight = settings.weight;
+        entries[i].active_from = active_from;
+        entries[i].active_until = active_until;
+        entries[i].breaks_only = settings.breaks_only.is_some();
+    })
+    .await
+}
+
+async fn move_sponsor_handler(
+    axum::extract::Path((file, direction)): axum::extract::Path<(String, String)>,
+) -> Result<Html<String>, StatusCode> {
+    let up = match direction.as_str() {
+        "up" => true,
+        "down" => false,
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+
+    // Files that couldn't be loaded stay in the manifest, sponsors move past them
+    let loaded: Vec<String> = SPONSORS
+        .lock()
+        .await
+        .iter()
+        .map(|s| s.entry.file.clone())
+        .collect();
+    let position = loaded
+        .iter()
+        .position(|f| *f == file)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let neighbor = match up {
+        true if position > 0 => loaded[position - 1].clone(),
+        false if position + 1 < loaded.len() => loaded[position + 1].clone(),
+        _ => return Ok(sponsor_list_handler().await),
+    };
+
+    update_sponsor_entry(&file, |entries, i| {
+        if let Some(j) = entries.iter().position(|e| e.file == neighbor) {
+            entries.swap(i, j);
+        }
+    })
+    .await
+}
+
+// endregion: --- Sponsor management
+// region: --- Countdown
+
+// A named countdown, the overlay shows the one that is selected
+#[derive(Serialize, Deserialize, Clone)]
+struct Countdown {
+    id: String,
+    title: String,
+    #[serde(flatten)]
+    target: CountdownTarget,
+    #[serde(default)]
+    on_complete: CountdownAction,
+    #[serde(skip)]
+    started: bool,
+    #[serde(skip)]
+    completed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum CountdownTarget {
+    // Seconds left, counts down while the countdown is started
+    Duration { secs: u64 },
+    // A local time (HH:MM for today, or a full date and time), counts down on its own
+    Time { at: String },
+}
+
+// What happens when the selected countdown reaches zero
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum CountdownAction {
+    // Stays on 0:00
+    #[default]
+    None,
+    // Hides the countdown from the overlay
+    Hide,
+    // Shows the text in place of the clock
+    Message {
+        text: String,
+    },
+    // Switches the overlay to a scene
+    Scene {
+        scene: String,
+    },
+}
+
+impl Countdown {
+    fn new(title: String, target: CountdownTarget, on_complete: CountdownAction) -> Countdown {
+        Countdown {
+            id: Uuid::new_v4().simple().to_string(),
+            title,
+            target,
+            on_complete,
+            started: false,
+            completed: false,
+        }
+    }
+
+    // Moves the countdown on by a second, returns whether it just reached zero
+    fn tick(&mut self, now: chrono::NaiveDateTime) -> bool {
+        if let CountdownTarget::Duration { secs } = &mut self.target {
+            if !self.started {
+                return false;
+            }
+            *secs = secs.saturating_sub(1);
+            if *secs == 0 {
+                self.started = false;
+            }
+        }
+
+        // A time of day counts down again once it rolls over to the next day
+        if self.remaining_secs(now) > 0 {
+            self.completed = false;
+            return false;
+        }
+
+        let finished = !self.completed;
+        self.completed = true;
+        finished
+    }
+
+    fn remaining_secs(&self, now: chrono::NaiveDateTime) -> u64 {
+        match &self.target {
+            CountdownTarget::Duration { secs } => *secs,
+            CountdownTarget::Time { at } => match parse_countdown_time(at, now) {
+                // Rounded up so it only shows 0:00 once the time is reached
+                Some(at) => ((at - now).num_milliseconds().max(0) as u64).div_ceil(1000),
+                None => 0,
+            },
+        }
+    }
+}
+
+fn default_countdowns() -> Vec<Countdown> {
+    vec![Countdown {
+        id: String::from("default"),
+        ..Countdown::new(
+            String::from("countdown"),
+            CountdownTarget::Duration { secs: 0 },
+            CountdownAction::None,
+        )
+    }]
+}
+
+// Seconds of a duration countdown, None when the minutes and seconds don't fit in a u64
+fn countdown_duration(mins: u64, secs: u64) -> Option<u64> {
+    mins.checked_mul(60)?.checked_add(secs)
+}
+
+// Parses the target of a time countdown, a time without a date is today
+fn parse_countdown_time(at: &str, now: chrono::NaiveDateTime) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveTime::parse_from_str(at, "%H:%M")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(at, "%H:%M:%S"))
+        .map(|time| now.date().and_time(time))
+        .ok()
+        .or_else(|| parse_local_datetime(at))
+}
+
+// Formats the time left like the game clock, hours are only shown when there are any
+fn format_countdown(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// Layout of the countdowns file
+#[derive(Serialize, Deserialize)]
+struct SavedCountdowns {
+    active: String,
+    countdowns: Vec<Countdown>,
+}
+
+async fn load_countdowns() {
+    let Ok(json) = tokio::fs::read_to_string(COUNTDOWNS_FILE).await else {
+        return;
+    };
+
+    match serde_json::from_str::<SavedCountdowns>(&json) {
+        Ok(saved) if !saved.countdowns.is_empty() => {
+            *ACTIVE_COUNTDOWN.lock().await =
+                if saved.countdowns.iter().any(|c| c.id == saved.active) {
+                    saved.active
+                } else {
+                    saved.countdowns[0].id.clone()
+                };
+            *COUNTDOWNS.lock().await = saved.countdowns;
+        }
+        Ok(_) => {}
+        Err(err) => warn!("COUNTDOWN: ignoring invalid {}: {}", COUNTDOWNS_FILE, err),
+    }
+}
+
+// Saves the countdowns without their running state, timers are stopped after a restart
+async fn save_countdowns(countdowns: &[Countdown], active: &str) -> Result<(), StatusCode> {
+    let saved = SavedCountdowns {
+        active: active.to_string(),
+        countdowns: countdowns.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&saved).expect("Failed to serialize countdowns");
+    tokio::fs::write(COUNTDOWNS_FILE, json)
+        .await
+        .map_err(|err| {
+            warn!("FAIL: could not save {}: {}", COUNTDOWNS_FILE, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// Changes the selected countdown, the manual countdown controls go through this
+async fn update_active_countdown(update: impl FnOnce(&mut Countdown)) {
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+    let mut countdowns = COUNTDOWNS.lock().await;
+
+    if let Some(countdown) = countdowns.iter_mut().find(|c| c.id == active) {
+        update(countdown);
+    }
+}
+
+// Changes the seconds left of the selected countdown if it is a duration countdown
+async fn update_active_duration(update: impl FnOnce(u64) -> u64) {
+    update_active_countdown(|countdown| {
+        if let CountdownTarget::Duration { secs } = &mut countdown.target {
+            *secs = update(*secs);
+            countdown.completed = false;
+        }
+    })
+    .await;
+}
+
+async fn countdown_ticker() {
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+        let now = chrono::Local::now().naive_local();
+        let active = ACTIVE_COUNTDOWN.lock().await.clone();
+        let mut finished = Vec::new();
+
+        for countdown in COUNTDOWNS.lock().await.iter_mut() {
+            if countdown.tick(now) {
+                finished.push((
+                    countdown.id.clone(),
+                    countdown.title.clone(),
+                    countdown.on_complete.clone(),
+                ));
+            }
+        }
+
+        for (id, title, on_complete) in finished {
+            info!("COUNTDOWN: {} finished", title);
+
+            // Only the countdown on the overlay changes what the overlay shows
+            if id == active {
+                match on_complete {
+                    CountdownAction::Hide => *SHOW_COUNTDOWN.lock().await = false,
+                    CountdownAction::Scene { scene } => {
+                        take_scene(&scene).await.unwrap_or_else(|status| {
+                            warn!("COUNTDOWN: could not take scene {}: {}", scene, status)
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+async fn start_countdown_handler() {
+    update_active_countdown(|countdown| countdown.started = true).await;
+}
+
+async fn stop_countdown_handler() {
+    update_active_countdown(|countdown| countdown.started = false).await;
+}
+
+// Returns the title of the selected countdown and what to show in place of its clock
+async fn active_countdown_display() -> (String, String) {
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+    let countdowns = COUNTDOWNS.lock().await;
+    let Some(countdown) = countdowns.iter().find(|c| c.id == active) else {
+        return (String::new(), format_countdown(0));
+    };
+
+    let clock = match &countdown.on_complete {
+        CountdownAction::Message { text } if countdown.completed => text.clone(),
+        _ => format_countdown(countdown.remaining_secs(chrono::Local::now().naive_local())),
+    };
+
+    (countdown.title.clone(), clock)
+}
+
+async fn countdown_display_handler() -> Html<String> {
+    let (title, clock) = active_countdown_display().await;
+
+    Html(format!(
+        "<h2 style=\"font-family: monospace;\">{}</h2> <br>
+         <p id=\"countdown-display-clock\" style=\"font-family: monospace; font-size: 150%;\">{}</p>
+    ",
+        html_escape(&title),
+        html_escape(&clock)
+    ))
+}
+
+async fn dashboard_countdown_display_handler() -> Html<String> {
+    let (_, clock) = active_countdown_display().await;
+    Html(html_escape(&clock))
+}
+
+async fn show_countdown_handler() {
+    let mut show_countdown = SHOW_COUNTDOWN.lock().await;
+    *show_countdown = !*show_countdown;
+}
+
+async fn countdown_css_handler() -> Html<&'static str> {
+    if *SHOW_COUNTDOWN.lock().await {
+        Html("<style> .white-boxes-container { display: none; } #show-countdown { background-color: rgb(227, 45, 32); } </style>")
+    } else {
+        Html("<style> .white-boxes-container { display: flex; } #show-countdown { background-color: #e9981f; } #countdown { display: none; }</style>")
+    }
+}
+
+async fn quick_countdown_handler(
+    axum::extract::Path((mins, secs)): axum::extract::Path<(u64, u64)>,
+) -> Result<(), StatusCode> {
+    let duration = countdown_duration(mins, secs).ok_or(StatusCode::BAD_REQUEST)?;
+    update_active_duration(|_| duration).await;
+    Ok(())
+}
+
+async fn countdown_mins_up_handler() {
+    update_active_duration(|secs| secs.saturating_add(60)).await;
+}
+
+async fn countdown_mins_down_handler() {
+    update_active_duration(|secs| if secs >= 60 { secs - 60 } else { secs }).await;
+}
+
+async fn countdown_secs_up_handler() {
+    update_active_duration(|secs| secs.saturating_add(1)).await;
+}
+
+async fn countdown_secs_down_handler() {
+    update_active_duration(|secs| secs.saturating_sub(1)).await;
+}
+
+#[derive(Deserialize)]
+struct CountdownTitle {
+    title: String,
+}
+
+async fn countdown_title_handler(
+    Form(title_data): Form<CountdownTitle>,
+) -> Result<Redirect, StatusCode> {
+    info!("COUNTDOWN: title set to {}", title_data.title);
+    update_active_countdown(|countdown| countdown.title = title_data.title).await;
+
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+    save_countdowns(&COUNTDOWNS.lock().await, &active).await?;
+    Ok(Redirect::to("/countdown"))
+}
+
+// A countdown as listed by the API, with the state that isn't saved
+#[derive(Serialize)]
+struct CountdownApiInfo {
+    #[serde(flatten)]
+    countdown: Countdown,
+    remaining_secs: u64,
+    started: bool,
+    completed: bool,
+    active: bool,
+}
+
+async fn list_countdowns_handler() -> Json<Vec<CountdownApiInfo>> {
+    let now = chrono::Local::now().naive_local();
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+
+    Json(
+        COUNTDOWNS
+            .lock()
+            .await
+            .iter()
+            .map(|countdown| CountdownApiInfo {
+                remaining_secs: countdown.remaining_secs(now),
+                started: countdown.started,
+                completed: countdown.completed,
+                active: countdown.id == active,
+                countdown: countdown.clone(),
+            })
+            .collect(),
+    )
+}
+
+// Handles and returns the countdown list of the countdown page
+async fn countdown_list_handler() -> Html<String> {
+    let now = chrono::Local::now().naive_local();
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+    let mut html = String::new();
+
+    for countdown in COUNTDOWNS.lock().await.iter() {
+        let target = match &countdown.target {
+            CountdownTarget::Duration { .. } => String::from("timer"),
+            CountdownTarget::Time { at } => format!("until {}", html_escape(at)),
+        };
+        let on_complete = match &countdown.on_complete {
+            CountdownAction::None => String::new(),
+            CountdownAction::Hide => String::from(", then hide"),
+            CountdownAction::Message { text } => format!(", then show \"{}\"", html_escape(text)),
+            CountdownAction::Scene { scene } => format!(", then switch to {}", html_escape(scene)),
+        };
+
+        html += &format!(
+            "
+            <div class=\"countdown-entry{}\">
+                <span>{} ({}{}) {}</span>
+                <button hx-post=\"/countdowns/{}/select\" hx-target=\"#countdown-list\">Select</button>
+                <button hx-delete=\"/countdowns/{}\" hx-target=\"#countdown-list\" hx-confirm=\"Delete this countdown?\">Delete</button>
+            </div>
+            ",
+            if countdown.id == active { " active" } else { "" },
+            html_escape(&countdown.title),
+            target,
+            on_complete,
+            format_countdown(countdown.remaining_secs(now)),
+            countdown.id,
+            countdown.id,
+        );
+    }
+
+    Html(html)
+}
+
+// Form to add a countdown, kind is duration (mins and secs) or time (at)
+#[derive(Deserialize)]
+struct CountdownForm {
+    title: String,
+    kind: String,
+    #[serde(default)]
+    mins: String,
+    #[serde(default)]
+    secs: String,
+    #[serde(default)]
+    at: String,
+    #[serde(default)]
+    on_complete: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    scene: String,
+}
+
+async fn add_countdown_handler(
+    Form(form): Form<CountdownForm>,
+) -> Result<Html<String>, StatusCode> {
+    let now = chrono::Local::now().naive_local();
+
+    // Empty number inputs count as zero
+    let number = |value: &str| match value.trim() {
+        "" => Ok(0),
+        value => value.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST),
+    };
+
+    let target = match form.kind.as_str() {
+        "duration" => CountdownTarget::Duration {
+            secs: countdown_duration(number(&form.mins)?, number(&form.secs)?)
+                .ok_or(StatusCode::BAD_REQUEST)?,
+        },
+        "time" if parse_countdown_time(form.at.trim(), now).is_some() => CountdownTarget::Time {
+            at: form.at.trim().to_string(),
+        },
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let on_complete = match form.on_complete.as_str() {
+        "" | "none" => CountdownAction::None,
+        "hide" => CountdownAction::Hide,
+        "message" => CountdownAction::Message { text: form.message },
+        "scene" if valid_scene_id(&form.scene) => CountdownAction::Scene { scene: form.scene },
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let countdown = Countdown::new(form.title.trim().to_string(), target, on_complete);
+    info!("COUNTDOWN: added {}", countdown.title);
+
+    let active = ACTIVE_COUNTDOWN.lock().await.clone();
+    let mut countdowns = COUNTDOWNS.lock().await;
+    countdowns.push(countdown);
+    save_countdowns(&countdowns, &active).await?;
+    drop(countdowns);
+
+    Ok(countdown_list_handler().await)
+}
+
+async fn select_countdown_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    let countdowns = COUNTDOWNS.lock().await;
+    if !countdowns.iter().any(|c| c.id == id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("COUNTDOWN: selected {}", id);
+    save_countdowns(&countdowns, &id).await?;
+    drop(countdowns);
+
+    *ACTIVE_COUNTDOWN.lock().await = id;
+    Ok(countdown_list_handler().await)
+}
+
+async fn delete_countdown_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    let mut countdowns = COUNTDOWNS.lock().await;
+    let index = countdowns
+        .iter()
+        .position(|c| c.id == id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // There is always a countdown to show
+    if countdowns.len() == 1 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    countdowns.remove(index);
+
+    let mut active = ACTIVE_COUNTDOWN.lock().await;
+    if *active == id {
+        *active = countdowns[0].id.clone();
+    }
+    save_countdowns(&countdowns, &active).await?;
+    drop(active);
+    drop(countdowns);
+
+    Ok(countdown_list_handler().await)
+}
+
+// endregion: --- Countdown
+// region: --- Popups
+
+// One of the two teams on the scoreboard, popups and lower thirds can belong to one
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TeamSide {
+    Home,
+    Away,
+}
+
+impl TeamSide {
+    fn side(self) -> &'static str {
+        match self {
+            TeamSide::Home => "home",
+            TeamSide::Away => "away",
+        }
+    }
+}
+
+// How a popup looks, team popups use the colors of their team
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PopupStyle {
+    #[default]
+    Default,
+    Alert,
+    Team,
+}
+
+// A popup that can be shown on the overlay, {team} and {player} in its text are filled in when it is queued
+#[derive(Clone, Serialize, Deserialize)]
+struct PopupDef {
+    #[serde(default)]
+    name: String,
+    text: String,
+    #[serde(default)]
+    team: Option<TeamSide>,
+    #[serde(default = "default_popup_duration")]
+    duration_secs: u64,
+    #[serde(default)]
+    style: PopupStyle,
+    // Higher priorities are shown first, popups with the same priority in the order they were queued
+    #[serde(default)]
+    priority: u8,
+}
+
+fn default_popup_duration() -> u64 {
+    4
+}
+
+impl PopupDef {
+    fn new(name: &str, text: &str, team: Option<TeamSide>, priority: u8) -> PopupDef {
+        PopupDef {
+            name: name.to_string(),
+            text: text.to_string(),
+            team,
+            duration_secs: default_popup_duration(),
+            style: PopupStyle::Default,
+            priority,
+        }
+    }
+
+    fn valid(&self) -> bool {
+        valid_popup_name(&self.name)
+            && !self.text.trim().is_empty()
+            && self.text.len() <= 200
+            && (1..=60).contains(&self.duration_secs)
+    }
+}
+
+// Popup names are used in urls
+fn valid_popup_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+// The popups the dashboard buttons use, config entries with the same name replace them
+fn builtin_popups() -> Vec<PopupDef> {
+    vec![
+        PopupDef::new("timeout", "Timeout", None, 10),
+        PopupDef::new("foul_home", "Foul: Home {player}", Some(TeamSide::Home), 5),
+        PopupDef::new("foul_away", "Foul: Away {player}", Some(TeamSide::Away), 5),
+        PopupDef::new("flag", "Flag on the play", None, 5),
+    ]
+}
+
+// Parses a popup config entry: name|text|team|duration_secs|style|priority, everything after the text is optional
+fn parse_popup_config(value: &str) -> Option<PopupDef> {
+    let parts: Vec<&str> = value.split('|').map(|part| part.trim()).collect();
+    let field = |i: usize| parts.get(i).copied().filter(|part| !part.is_empty());
+
+    let popup = PopupDef {
+        name: field(0)?.to_string(),
+        text: field(1)?.to_string(),
+        team: match field(2) {
+            None => None,
+            Some("home") => Some(TeamSide::Home),
+            Some("away") => Some(TeamSide::Away),
+            Some(_) => return None,
+        },
+        duration_secs: match field(3) {
+            None => default_popup_duration(),
+            Some(secs) => secs.parse().ok()?,
+        },
+        style: match field(4) {
+            None | Some("default") => PopupStyle::Default,
+            Some("alert") => PopupStyle::Alert,
+            Some("team") => PopupStyle::Team,
+            Some(_) => return None,
+        },
+        priority: match field(5) {
+            None => 0,
+            Some(priority) => priority.parse().ok()?,
+        },
+    };
+
+    popup.valid().then_some(popup)
+}
+
+async fn load_popups() {
+    let Ok(json) = tokio::fs::read_to_string(POPUPS_FILE).await else {
+        return;
+    };
+
+    match serde_json::from_str::<Vec<PopupDef>>(&json) {
+        Ok(popups) => *API_POPUPS.lock().await = popups.into_iter().filter(|p| p.valid()).collect(),
+        Err(err) => warn!("POPUP: ignoring invalid {}: {}", POPUPS_FILE, err),
+    }
+}
+
+async fn save_popups(popups: &[PopupDef]) -> Result<(), StatusCode> {
+    let json = serde_json::to_string_pretty(popups).expect("Failed to serialize popups");
+    tokio::fs::write(POPUPS_FILE, json).await.map_err(|err| {
+        warn!("FAIL: could not save {}: {}", POPUPS_FILE, err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// Looks up a popup, the ones from the config come first
+async fn find_popup(name: &str) -> Option<PopupDef> {
+    if let Some(popup) = CONFIG_POPUPS.lock().await.iter().find(|p| p.name == name) {
+        return Some(popup.clone());
+    }
+    API_POPUPS
+        .lock()
+        .await
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+}
 
-    while let Ok(Some(res)) = team_presets.next_entry().await {
-        if !res.file_name().to_string_lossy().to_string().contains(".") {
-            valid_ids.push(res.file_name().into_string().unwrap());
+// A popup waiting in the queue or on screen, with its text filled in
+#[derive(Clone, Serialize)]
+struct QueuedPopup {
+    name: String,
+    text: String,
+    team: Option<TeamSide>,
+    duration_secs: u64,
+    style: PopupStyle,
+    priority: u8,
+}
+
+// Fills in the popup's text and adds it to the queue
+async fn queue_popup(popup: &PopupDef, player: Option<&Player>) -> StatusCode {
+    let team = match popup.team {
+        Some(TeamSide::Home) => HOME_NAME.lock().await.clone(),
+        Some(TeamSide::Away) => AWAY_NAME.lock().await.clone(),
+        None => String::new(),
+    };
+    let player = player.map(|p| p.label()).unwrap_or_default();
+    let text = popup
+        .text
+        .replace("{team}", &team)
+        .replace("{player}", &player)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let mut queue = POPUP_QUEUE.lock().await;
+    if queue.len() >= MAX_QUEUED_POPUPS {
+        warn!("FAIL: popup queue is full, dropped {}", popup.name);
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
+
+    info!("POPUP: queued {}", popup.name);
+    queue.push(QueuedPopup {
+        name: popup.name.clone(),
+        text,
+        team: popup.team,
+        duration_secs: popup.duration_secs,
+        style: popup.style,
+        priority: popup.priority,
+    });
+    drop(queue);
+
+    POPUP_WAKE.notify_one();
+    StatusCode::OK
+}
+
+// The first of the queued popups with the highest priority
+fn next_popup_index(queue: &[QueuedPopup]) -> Option<usize> {
+    queue
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, popup)| (popup.priority, std::cmp::Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+// Shows the queued popups one after another, it sleeps while the queue is empty and is woken up by POPUP_WAKE
+async fn popup_ticker() {
+    loop {
+        let next = {
+            let mut queue = POPUP_QUEUE.lock().await;
+            next_popup_index(&queue).map(|i| queue.remove(i))
+        };
+        let Some(popup) = next else {
+            POPUP_WAKE.notified().await;
+            continue;
+        };
+
+        // Sponsors that only run during breaks can show while a timeout is on screen
+        let timeout = popup.name == "timeout";
+        let until = tokio::time::Instant::now() + Duration::from_secs(popup.duration_secs);
+        *CURRENT_POPUP.lock().await = Some(popup);
+        if timeout {
+            SPONSOR_ROLL_WAKE.notify_one();
+        }
+
+        // Clearing the popups takes the current one off screen early
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(until) => break,
+                _ = POPUP_WAKE.notified() => {
+                    if CURRENT_POPUP.lock().await.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        *CURRENT_POPUP.lock().await = None;
+        if timeout {
+            SPONSOR_ROLL_WAKE.notify_one();
         }
     }
+}
 
-    for i in &valid_ids {
-        let team_info_json = tokio::fs::read_to_string(format!("./teams/{}/teaminfo.json", i))
-            .await
-            .expect("Id doesnt exist!");
-        let team_info: TeamInfoContainer =
-            serde_json::from_str(&team_info_json).expect("Could not deserialize data!");
+// True while the timeout popup is on screen
+async fn timeout_showing() -> bool {
+    CURRENT_POPUP
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|popup| popup.name == "timeout")
+}
 
-        let home_img_bytes = tokio::fs::read(format!("./teams/{}/home.png", i))
-            .await
-            .unwrap();
-        let away_img_bytes = tokio::fs::read(format!("./teams/{}/away.png", i))
+async fn clear_popups() {
+    POPUP_QUEUE.lock().await.clear();
+    *CURRENT_POPUP.lock().await = None;
+    POPUP_WAKE.notify_one();
+}
+
+async fn popup_handler(
+    axum::extract::Path(name): axum::extract::Path<String>,
+    player: Option<Form<PlayerRef>>,
+) -> StatusCode {
+    let Some(popup) = find_popup(&name).await else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    // Team popups can name a player from that team's roster
+    let player = match popup.team {
+        Some(team) => picked_player(team.side(), player).await,
+        None => Ok(None),
+    };
+    let player = match player {
+        Ok(player) => player,
+        Err(status) => return status,
+    };
+
+    // Every foul counts in the box score, even if its popup has to wait in the queue
+    if let "foul_home" | "foul_away" = name.as_str() {
+        push_event(
+            &name[5..],
+            GameAction::Foul {
+                player: player.clone(),
+            },
+        )
+        .await;
+    }
+
+    queue_popup(&popup, player.as_ref()).await
+}
+
+async fn popup_show_handler() -> Html<String> {
+    match &*CURRENT_POPUP.lock().await {
+        Some(popup) => Html(format!("<p>{}</p>", html_escape(&popup.text))),
+        None => Html(String::new()),
+    }
+}
+
+// Hides the popup container while no popup is showing and sets the colors of the one that is
+async fn popup_css_handler() -> Html<String> {
+    let Some(popup) = CURRENT_POPUP.lock().await.clone() else {
+        return Html(String::from(
+            "<style> .popup-container { display: none; } </style>",
+        ));
+    };
+
+    match (popup.style, popup.team) {
+        (PopupStyle::Alert, _) => Html(String::from(
+            "<style> :root { --popup-background: rgb(190, 20, 20); --popup-text: white; --popup-accent: white; } </style>",
+        )),
+        (PopupStyle::Team, Some(team)) => Html(format!(
+            "<style> :root {{ --popup-background: var(--{0}-primary); --popup-text: var(--{0}-text); --popup-accent: var(--{0}-secondary); }} </style>",
+            team.side()
+        )),
+        _ => Html(String::new()),
+    }
+}
+
+// Handles and returns the dashboard buttons for the popups that have no button of their own
+async fn popup_buttons_handler() -> Html<String> {
+    let mut popups = CONFIG_POPUPS.lock().await.clone();
+    popups.extend(API_POPUPS.lock().await.iter().cloned());
+
+    let mut html = String::new();
+    for popup in popups
+        .iter()
+        .filter(|p| !matches!(p.name.as_str(), "timeout" | "foul_home" | "foul_away"))
+    {
+        let include = match popup.team {
+            Some(team) => format!(" hx-include=\"#{}_player\"", team.side()),
+            None => String::new(),
+        };
+        html += &format!(
+            "<button hx-post=\"/popup/{}\" hx-swap=\"none\"{}>{}</button>",
+            popup.name,
+            include,
+            html_escape(&popup.name.replace('_', " ")),
+        );
+    }
+
+    Html(html)
+}
+
+async fn clear_popups_handler() -> StatusCode {
+    info!("POPUP: cleared");
+    clear_popups().await;
+    StatusCode::OK
+}
+
+// A popup as listed by the API, popups from the config can only be changed in the config
+#[derive(Serialize)]
+struct PopupApiInfo {
+    #[serde(flatten)]
+    popup: PopupDef,
+    source: &'static str,
+}
+
+async fn list_popups_handler() -> Json<Vec<PopupApiInfo>> {
+    let mut popups: Vec<PopupApiInfo> = CONFIG_POPUPS
+        .lock()
+        .await
+        .iter()
+        .map(|popup| PopupApiInfo {
+            popup: popup.clone(),
+            source: "config",
+        })
+        .collect();
+    popups.extend(API_POPUPS.lock().await.iter().map(|popup| PopupApiInfo {
+        popup: popup.clone(),
+        source: "api",
+    }));
+
+    Json(popups)
+}
+
+async fn put_popup_handler(
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(mut popup): Json<PopupDef>,
+) -> StatusCode {
+    popup.name = name;
+    if !popup.valid() {
+        return StatusCode::BAD_REQUEST;
+    }
+    if CONFIG_POPUPS
+        .lock()
+        .await
+        .iter()
+        .any(|p| p.name == popup.name)
+    {
+        return StatusCode::CONFLICT;
+    }
+
+    info!("POPUP: saved {}", popup.name);
+    let mut popups = API_POPUPS.lock().await;
+    match popups.iter_mut().find(|p| p.name == popup.name) {
+        Some(existing) => *existing = popup,
+        None => popups.push(popup),
+    }
+    match save_popups(&popups).await {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
+    }
+}
+
+async fn delete_popup_handler(
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> StatusCode {
+    let mut popups = API_POPUPS.lock().await;
+    let Some(index) = popups.iter().position(|p| p.name == name) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    info!("POPUP: deleted {}", name);
+    popups.remove(index);
+    match save_popups(&popups).await {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
+    }
+}
+
+// The popup on screen and the ones waiting, in the order they will be shown
+#[derive(Serialize)]
+struct PopupQueueInfo {
+    current: Option<QueuedPopup>,
+    queued: Vec<QueuedPopup>,
+}
+
+async fn popup_queue_handler() -> Json<PopupQueueInfo> {
+    let mut queued = POPUP_QUEUE.lock().await.clone();
+    // A stable sort keeps popups with the same priority in the order they were queued
+    queued.sort_by_key(|popup| std::cmp::Reverse(popup.priority));
+
+    Json(PopupQueueInfo {
+        current: CURRENT_POPUP.lock().await.clone(),
+        queued,
+    })
+}
+
+// Queues a one-off popup that is not saved
+async fn queue_custom_popup_handler(Json(mut popup): Json<PopupDef>) -> StatusCode {
+    if popup.name.is_empty() {
+        popup.name = String::from("custom");
+    }
+    if !popup.valid() {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    queue_popup(&popup, None).await
+}
+
+// endregion: --- Popups
+// region: --- Lower thirds
+
+// A lower third in the library, the image is a file in ./lower_thirds
+#[derive(Clone, Serialize, Deserialize)]
+struct LowerThird {
+    #[serde(default)]
+    id: String,
+    title: String,
+    #[serde(default)]
+    subtitle: String,
+    #[serde(default)]
+    image: Option<String>,
+    // The lower third uses the colors of this team
+    #[serde(default)]
+    team: Option<TeamSide>,
+    // Taken off air after this many seconds, 0 keeps it on until it is cleared
+    #[serde(default)]
+    auto_clear_secs: u64,
+    #[serde(skip)]
+    image_url: Option<String>,
+}
+
+impl LowerThird {
+    fn valid(&self) -> bool {
+        !self.title.trim().is_empty()
+            && self.title.len() <= 100
+            && self.subtitle.len() <= 200
+            && self.auto_clear_secs <= 3600
+    }
+}
+
+// The lower third on air and when it is cleared by itself
+struct OnAirLowerThird {
+    lower_third: LowerThird,
+    until: Option<tokio::time::Instant>,
+}
+
+// Lower third images are stored as <id>.png or <id>.svg
+fn valid_lower_third_image(file: &str) -> bool {
+    file.strip_suffix(".png")
+        .or_else(|| file.strip_suffix(".svg"))
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+async fn load_lower_thirds() {
+    let Ok(json) = tokio::fs::read_to_string(LOWER_THIRDS_FILE).await else {
+        return;
+    };
+
+    let mut lower_thirds = match serde_json::from_str::<Vec<LowerThird>>(&json) {
+        Ok(lower_thirds) => lower_thirds,
+        Err(err) => {
+            warn!(
+                "LOWER THIRD: ignoring invalid {}: {}",
+                LOWER_THIRDS_FILE, err
+            );
+            return;
+        }
+    };
+
+    for lower_third in lower_thirds.iter_mut() {
+        let Some(image) = lower_third
+            .image
+            .clone()
+            .filter(|f| valid_lower_third_image(f))
+        else {
+            lower_third.image = None;
+            continue;
+        };
+        match tokio::fs::read(format!("./lower_thirds/{}", image)).await {
+            Ok(bytes) => {
+                lower_third.image_url =
+                    Some(cache_image(format!("lower_third:{}", lower_third.id), bytes).await)
+            }
+            Err(err) => {
+                warn!("LOWER THIRD: missing image {}: {}", image, err);
+                lower_third.image = None;
+            }
+        }
+    }
+
+    *LOWER_THIRDS.lock().await = lower_thirds;
+}
+
+async fn save_lower_thirds(lower_thirds: &[LowerThird]) -> Result<(), StatusCode> {
+    let json =
+        serde_json::to_string_pretty(lower_thirds).expect("Failed to serialize lower thirds");
+    tokio::fs::write(LOWER_THIRDS_FILE, json)
+        .await
+        .map_err(|err| {
+            warn!("FAIL: could not save {}: {}", LOWER_THIRDS_FILE, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// Puts a lower third on air, replacing the one that is on
+async fn take_lower_third(lower_third: LowerThird) {
+    info!("LOWER THIRD: took {}", lower_third.title);
+    let until = (lower_third.auto_clear_secs > 0)
+        .then(|| tokio::time::Instant::now() + Duration::from_secs(lower_third.auto_clear_secs));
+
+    *ON_AIR_LOWER_THIRD.lock().await = Some(OnAirLowerThird { lower_third, until });
+    LOWER_THIRD_WAKE.notify_one();
+}
+
+// Clears lower thirds when their time is up, it sleeps while nothing has to be cleared and is woken up by LOWER_THIRD_WAKE
+async fn lower_third_ticker() {
+    loop {
+        let until = ON_AIR_LOWER_THIRD
+            .lock()
             .await
-            .unwrap();
+            .as_ref()
+            .and_then(|on_air| on_air.until);
 
-        inject_html += &format!(
+        let Some(until) = until else {
+            LOWER_THIRD_WAKE.notified().await;
+            continue;
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep_until(until) => {
+                // Only clear it if it wasn't replaced in the meantime
+                let mut on_air = ON_AIR_LOWER_THIRD.lock().await;
+                if on_air.as_ref().is_some_and(|o| o.until == Some(until)) {
+                    info!("LOWER THIRD: cleared after its time");
+                    *on_air = None;
+                }
+            }
+            _ = LOWER_THIRD_WAKE.notified() => {}
+        }
+    }
+}
+
+// Handles and returns the lower third on the overlay
+async fn lower_third_handler() -> Html<String> {
+    let on_air = ON_AIR_LOWER_THIRD.lock().await;
+    let Some(OnAirLowerThird { lower_third, .. }) = &*on_air else {
+        return Html(String::new());
+    };
+
+    let image = match &lower_third.image_url {
+        Some(url) => format!("<img src=\"{}\" alt=\"\">", url),
+        None => String::new(),
+    };
+    let subtitle = if lower_third.subtitle.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<p class=\"lower-third-subtitle\">{}</p>",
+            html_escape(&lower_third.subtitle)
+        )
+    };
+
+    Html(format!(
+        "{}<div class=\"lower-third-text\"><p class=\"lower-third-title\">{}</p>{}</div>",
+        image,
+        html_escape(&lower_third.title),
+        subtitle
+    ))
+}
+
+// Hides the lower third while nothing is on air and sets the colors of its team
+async fn lower_third_css_handler() -> Html<String> {
+    match &*ON_AIR_LOWER_THIRD.lock().await {
+        None => Html(String::from(
+            "<style> .lower-third { display: none; } </style>",
+        )),
+        Some(OnAirLowerThird {
+            lower_third: LowerThird {
+                team: Some(team), ..
+            },
+            ..
+        }) => Html(format!(
+            "<style> :root {{ --lower-third-background: var(--{0}-primary); --lower-third-text: var(--{0}-text); --lower-third-accent: var(--{0}-secondary); }} </style>",
+            team.side()
+        )),
+        Some(_) => Html(String::new()),
+    }
+}
+
+// Handles and returns the lower third library of the lower thirds page
+async fn lower_third_list_handler() -> Html<String> {
+    let on_air_id = ON_AIR_LOWER_THIRD
+        .lock()
+        .await
+        .as_ref()
+        .map(|on_air| on_air.lower_third.id.clone());
+    let mut html = String::new();
+
+    for lower_third in LOWER_THIRDS.lock().await.iter() {
+        let image = match &lower_third.image_url {
+            Some(url) => format!("<img src=\"{}\" height=\"40px\" width=\"auto\">", url),
+            None => String::new(),
+        };
+        let team = match lower_third.team {
+            Some(team) => format!(", {}", team.side()),
+            None => String::new(),
+        };
+        let auto_clear = match lower_third.auto_clear_secs {
+            0 => String::new(),
+            secs => format!(", clears after {}s", secs),
+        };
+
+        html += &format!(
             "
-            <div class=\"match-selector\">
-                <p>{} vs. {}</p>
-                <div style=\"display: inline\">
-                <img src=\"data:image/png;base64,{}\" height=\"30px\" width=\"auto\" style=\"margin-right: 15px;\"/>
-                    <img src=\"data:image/png;base64,{}\" height=\"30px\" width=\"auto\" style=\"margin-right: 15px;\"/>
-                </div>
-                <br>
-                <button hx-post=\"/load_team/{}\" hx-swap=\"none\" style=\"width: 100%;\">Select</button>
-                <button hx-post=\"/delete_preset/{}\" hx-swap=\"none\" style=\"width: 100%; margin-top: 15px;\">Remove</button>
+            <div class=\"lower-third-entry{}\">
+                {}
+                <span><strong>{}</strong> {}{}{}</span>
+                <button hx-post=\"/lower_thirds/{}/take\" hx-target=\"#lower-third-list\">Take</button>
+                <button hx-delete=\"/lower_thirds/{}\" hx-target=\"#lower-third-list\" hx-confirm=\"Delete this lower third?\">Delete</button>
             </div>
-        ",
-            team_info.home_name,
-            team_info.away_name,
-            BASE64_STANDARD.encode(home_img_bytes),
-            BASE64_STANDARD.encode(away_img_bytes),
-            i,
-            i
+            ",
+            if on_air_id.as_ref() == Some(&lower_third.id) { " active" } else { "" },
+            image,
+            html_escape(&lower_third.title),
+            html_escape(&lower_third.subtitle),
+            team,
+            auto_clear,
+            lower_third.id,
+            lower_third.id,
         );
     }
 
-    dbg!(valid_ids);
+    Html(html)
+}
+
+// Adds a lower third to the library from a multipart form, the image field is optional
+async fn add_lower_third_handler(mut payload: Multipart) -> Result<Html<String>, StatusCode> {
+    let mut lower_third = LowerThird {
+        id: Uuid::new_v4().simple().to_string(),
+        title: String::new(),
+        subtitle: String::new(),
+        image: None,
+        team: None,
+        auto_clear_secs: 0,
+        image_url: None,
+    };
+    let mut image = None;
+
+    loop {
+        let field = match payload.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                warn!("FAIL: lower third upload: {}", err.body_text());
+                return Err(err.status());
+            }
+        };
+
+        let name = field.name().unwrap_or_default().to_string();
+        let data = match field.bytes().await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("FAIL: lower third upload: {}", err.body_text());
+                return Err(err.status());
+            }
+        };
+        let text = || String::from_utf8_lossy(&data).trim().to_string();
+
+        match name.as_str() {
+            "title" => lower_third.title = text(),
+            "subtitle" => lower_third.subtitle = text(),
+            "team" => {
+                lower_third.team = match text().as_str() {
+                    "" => None,
+                    "home" => Some(TeamSide::Home),
+                    "away" => Some(TeamSide::Away),
+                    _ => return Err(StatusCode::BAD_REQUEST),
+                }
+            }
+            "auto_clear_secs" => {
+                lower_third.auto_clear_secs = match text().as_str() {
+                    "" => 0,
+                    secs => secs.parse().map_err(|_| StatusCode::BAD_REQUEST)?,
+                }
+            }
+            // Browsers send an empty file when none was picked
+            "image" if data.is_empty() => {}
+            "image" => image = Some(data),
+            _ => {
+                warn!(
+                    "FAIL: lower third upload has an unexpected field {:?}",
+                    name
+                );
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+
+    if !lower_third.valid() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if let Some(data) = image {
+        let Some((kind, data)) = process_image(data.to_vec()).await else {
+            warn!("FAIL: lower third image is not an image");
+            return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        };
+        let file = format!("{}.{}", lower_third.id, kind.file_extension());
+        if let Err(err) = tokio::fs::write(format!("./lower_thirds/{}", file), &data).await {
+            warn!("FAIL: could not write lower third image {}: {}", file, err);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        lower_third.image = Some(file);
+        lower_third.image_url =
+            Some(cache_image(format!("lower_third:{}", lower_third.id), data).await);
+    }
+
+    info!("LOWER THIRD: added {}", lower_third.title);
+    let mut lower_thirds = LOWER_THIRDS.lock().await;
+    lower_thirds.push(lower_third);
+    save_lower_thirds(&lower_thirds).await?;
+    drop(lower_thirds);
+
+    Ok(lower_third_list_handler().await)
+}
+
+async fn delete_lower_third_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    let mut lower_thirds = LOWER_THIRDS.lock().await;
+    let index = lower_thirds
+        .iter()
+        .position(|l| l.id == id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let lower_third = lower_thirds.remove(index);
+    save_lower_thirds(&lower_thirds).await?;
+    drop(lower_thirds);
+
+    if let Some(image) = lower_third.image {
+        let _ = tokio::fs::remove_file(format!("./lower_thirds/{}", image)).await;
+    }
+    release_image(&format!("lower_third:{}", lower_third.id)).await;
+    info!("LOWER THIRD: deleted {}", lower_third.title);
+
+    Ok(lower_third_list_handler().await)
+}
+
+async fn take_lower_third_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    let lower_third = LOWER_THIRDS
+        .lock()
+        .await
+        .iter()
+        .find(|l| l.id == id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    take_lower_third(lower_third).await;
+    Ok(lower_third_list_handler().await)
+}
+
+async fn clear_lower_third_handler() -> Html<String> {
+    info!("LOWER THIRD: cleared");
+    *ON_AIR_LOWER_THIRD.lock().await = None;
+    LOWER_THIRD_WAKE.notify_one();
+
+    lower_third_list_handler().await
+}
+
+// A lower third as returned by the API, with a link to its image
+#[derive(Serialize)]
+struct LowerThirdApiInfo {
+    #[serde(flatten)]
+    lower_third: LowerThird,
+    image_url: Option<String>,
+}
+
+impl From<&LowerThird> for LowerThirdApiInfo {
+    fn from(lower_third: &LowerThird) -> LowerThirdApiInfo {
+        LowerThirdApiInfo {
+            lower_third: lower_third.clone(),
+            image_url: lower_third.image_url.clone(),
+        }
+    }
+}
+
+async fn list_lower_thirds_handler() -> Json<Vec<LowerThirdApiInfo>> {
+    Json(LOWER_THIRDS.lock().await.iter().map(Into::into).collect())
+}
+
+async fn on_air_lower_third_handler() -> Json<Option<LowerThirdApiInfo>> {
+    Json(
+        ON_AIR_LOWER_THIRD
+            .lock()
+            .await
+            .as_ref()
+            .map(|on_air| (&on_air.lower_third).into()),
+    )
+}
+
+// Takes a one-off lower third that is not saved, for example a score announcement
+async fn take_custom_lower_third_handler(Json(mut lower_third): Json<LowerThird>) -> StatusCode {
+    if !lower_third.valid() {
+        return StatusCode::BAD_REQUEST;
+    }
+    lower_third.image = None;
+    lower_third.image_url = None;
+
+    take_lower_third(lower_third).await;
+    StatusCode::OK
+}
+
+// endregion: --- Lower thirds
+// region: --- Scenes
+
+// The parts of the overlay a scene can show, hide and move
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Widget {
+    Scorebug,
+    Countdown,
+    Sponsors,
+    Stats,
+    LowerThird,
+    Popups,
+}
+
+impl Widget {
+    fn selector(self) -> &'static str {
+        match self {
+            Widget::Scorebug => ".white-boxes-container",
+            Widget::Countdown => "#countdown",
+            Widget::Sponsors => ".sponsor-container",
+            Widget::Stats => ".stats-panel",
+            Widget::LowerThird => ".lower-third",
+            Widget::Popups => ".popup-container",
+        }
+    }
+}
+
+// Auto leaves a widget to its own toggles, shown turns the countdown and sponsors on when the scene is taken
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WidgetVisibility {
+    Hidden,
+    #[default]
+    Auto,
+    Shown,
+}
+
+// Where a widget is anchored on screen, default keeps it where the overlay puts it
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WidgetPosition {
+    #[default]
+    Default,
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl WidgetPosition {
+    // Returns the top, right, bottom and left offsets, the translation that puts the anchor there and the transform origin
+    fn css(self) -> Option<([&'static str; 4], &'static str, &'static str)> {
+        Some(match self {
+            WidgetPosition::Default => return None,
+            WidgetPosition::TopLeft => (["2%", "auto", "auto", "2%"], "", "top left"),
+            WidgetPosition::Top => (
+                ["2%", "auto", "auto", "50%"],
+                "translateX(-50%) ",
+                "top center",
+            ),
+            WidgetPosition::TopRight => (["2%", "2%", "auto", "auto"], "", "top right"),
+            WidgetPosition::Left => (
+                ["50%", "auto", "auto", "2%"],
+                "translateY(-50%) ",
+                "center left",
+            ),
+            WidgetPosition::Center => (
+                ["50%", "auto", "auto", "50%"],
+                "translate(-50%, -50%) ",
+                "center",
+            ),
+            WidgetPosition::Right => (
+                ["50%", "2%", "auto", "auto"],
+                "translateY(-50%) ",
+                "center right",
+            ),
+            WidgetPosition::BottomLeft => (["auto", "auto", "2%", "2%"], "", "bottom left"),
+            WidgetPosition::Bottom => (
+                ["auto", "auto", "2%", "50%"],
+                "translateX(-50%) ",
+                "bottom center",
+            ),
+            WidgetPosition::BottomRight => (["auto", "2%", "2%", "auto"], "", "bottom right"),
+        })
+    }
+}
+
+// How a scene shows one widget
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct WidgetLayout {
+    #[serde(default)]
+    visibility: WidgetVisibility,
+    #[serde(default)]
+    position: WidgetPosition,
+    #[serde(default = "default_widget_scale")]
+    scale: f32,
+}
+
+fn default_widget_scale() -> f32 {
+    1.0
+}
+
+impl WidgetLayout {
+    fn new(visibility: WidgetVisibility, position: WidgetPosition, scale: f32) -> WidgetLayout {
+        WidgetLayout {
+            visibility,
+            position,
+            scale,
+        }
+    }
+
+    // The css that hides, shows or moves the widget
+    fn css(&self, widget: Widget) -> String {
+        let selector = widget.selector();
+        let mut rules = Vec::new();
+
+        match self.visibility {
+            WidgetVisibility::Hidden => rules.push(String::from("display: none !important;")),
+            // The scorebug is hidden while the countdown is shown unless the scene shows it
+            WidgetVisibility::Shown if widget == Widget::Scorebug => {
+                rules.push(String::from("display: flex !important;"))
+            }
+            _ => {}
+        }
+
+        // Everything is important since the countdown is positioned with an inline style
+        match self.position.css() {
+            Some(([top, right, bottom, left], translate, origin)) => rules.push(format!(
+                "position: fixed !important; top: {} !important; right: {} !important; bottom: {} !important; left: {} !important; width: auto !important; transform: {}scale({}) !important; transform-origin: {};",
+                top, right, bottom, left, translate, self.scale, origin
+            )),
+            None if self.scale != 1.0 => rules.push(format!("scale: {};", self.scale)),
+            None => {}
+        }
+
+        if rules.is_empty() {
+            String::new()
+        } else {
+            format!("{} {{ {} }} ", selector, rules.join(" "))
+        }
+    }
+}
+
+// A named layout of the overlay, widgets that are left out follow their own toggles
+#[derive(Clone, Serialize, Deserialize)]
+struct Scene {
+    #[serde(default)]
+    id: String,
+    title: String,
+    #[serde(default)]
+    widgets: BTreeMap<Widget, WidgetLayout>,
+}
+
+impl Scene {
+    fn new(id: &str, title: &str, widgets: &[(Widget, WidgetLayout)]) -> Scene {
+        Scene {
+            id: id.to_string(),
+            title: title.to_string(),
+            widgets: widgets.iter().copied().collect(),
+        }
+    }
+
+    fn valid(&self) -> bool {
+        valid_scene_id(&self.id)
+            && !self.title.trim().is_empty()
+            && self.title.len() <= 64
+            && self
+                .widgets
+                .values()
+                .all(|layout| (0.25..=4.0).contains(&layout.scale))
+    }
 
-    Html::from(inject_html)
+    fn visibility(&self, widget: Widget) -> WidgetVisibility {
+        self.widgets
+            .get(&widget)
+            .map(|layout| layout.visibility)
+            .unwrap_or_default()
+    }
 }
 
-async fn load_team_handler(axum::extract::Path(id): axum::extract::Path<String>) {
-    let team_info_json = tokio::fs::read_to_string(format!("./teams/{}/teaminfo.json", id))
-        .await
-        .expect("Id doesnt exist!");
+// Scene ids are used in urls
+fn valid_scene_id(id: &str) -> bool {
+    (1..=32).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
 
-    let team_info: TeamInfoContainer =
-        serde_json::from_str(&team_info_json).expect("Could not deserialize data!");
+// The scenes there are before any are added, in game is the overlay as it has always looked
+fn default_scenes() -> Vec<Scene> {
+    use WidgetPosition as P;
+    use WidgetVisibility as V;
+    let hidden = WidgetLayout::new(V::Hidden, P::Default, 1.0);
+    let shown = WidgetLayout::new(V::Shown, P::Default, 1.0);
+
+    vec![
+        Scene::new(
+            "pregame",
+            "Pregame",
+            &[
+                (Widget::Scorebug, hidden),
+                (
+                    Widget::Countdown,
+                    WidgetLayout::new(V::Shown, P::Center, 2.0),
+                ),
+                (Widget::Sponsors, shown),
+                (Widget::Stats, hidden),
+            ],
+        ),
+        Scene::new("in_game", "In game", &[]),
+        Scene::new(
+            "halftime",
+            "Halftime stats",
+            &[
+                (
+                    Widget::Scorebug,
+                    WidgetLayout::new(V::Shown, P::Bottom, 1.0),
+                ),
+                (Widget::Stats, shown),
+                (Widget::Popups, hidden),
+            ],
+        ),
+        Scene::new(
+            "final",
+            "Final score",
+            &[
+                (
+                    Widget::Scorebug,
+                    WidgetLayout::new(V::Shown, P::Center, 2.0),
+                ),
+                (Widget::Countdown, hidden),
+                (Widget::Stats, hidden),
+                (Widget::Popups, hidden),
+            ],
+        ),
+        Scene::new(
+            "sponsors",
+            "Sponsor fullscreen",
+            &[
+                (Widget::Scorebug, hidden),
+                (Widget::Countdown, hidden),
+                (
+                    Widget::Sponsors,
+                    WidgetLayout::new(V::Shown, P::Center, 4.0),
+                ),
+                (Widget::Stats, hidden),
+                (Widget::LowerThird, hidden),
+                (Widget::Popups, hidden),
+            ],
+        ),
+    ]
+}
 
-    println!(" -> LOAD: match {:?}", team_info);
+// Layout of the scenes file
+#[derive(Serialize, Deserialize)]
+struct SavedScenes {
+    active: String,
+    scenes: Vec<Scene>,
+}
 
-    *HOME_NAME.lock().await = team_info.home_name;
-    *AWAY_NAME.lock().await = team_info.away_name;
+async fn load_scenes() {
+    let Ok(json) = tokio::fs::read_to_string(SCENES_FILE).await else {
+        return;
+    };
 
-    *HOME_IMG_DATA.lock().await = tokio::fs::read(format!("./teams/{}/home.png", id))
-        .await
-        .unwrap();
-    *AWAY_IMG_DATA.lock().await = tokio::fs::read(format!("./teams/{}/away.png", id))
-        .await
-        .unwrap();
+    match serde_json::from_str::<SavedScenes>(&json) {
+        Ok(saved) if !saved.scenes.is_empty() => {
+            *ACTIVE_SCENE.lock().await = if saved.scenes.iter().any(|s| s.id == saved.active) {
+                saved.active
+            } else {
+                saved.scenes[0].id.clone()
+            };
+            *SCENES.lock().await = saved.scenes;
+        }
+        Ok(_) => {}
+        Err(err) => warn!("SCENE: ignoring invalid {}: {}", SCENES_FILE, err),
+    }
 }
 
-// Handles the file upload for the team's logo
-async fn add_team_handler(mut payload: Multipart) -> impl IntoResponse {
-    const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+async fn save_scenes(scenes: &[Scene], active: &str) -> Result<(), StatusCode> {
+    let saved = SavedScenes {
+        active: active.to_string(),
+        scenes: scenes.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&saved).expect("Failed to serialize scenes");
+    tokio::fs::write(SCENES_FILE, json).await.map_err(|err| {
+        warn!("FAIL: could not save {}: {}", SCENES_FILE, err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
 
-    let mut home_name = String::from("");
-    let mut home_color = String::from("");
+async fn active_scene() -> Option<Scene> {
+    let active = ACTIVE_SCENE.lock().await.clone();
+    SCENES.lock().await.iter().find(|s| s.id == active).cloned()
+}
 
-    let mut away_name = String::from("");
-    let mut away_color = String::from("");
+// How the active scene shows a widget
+async fn scene_visibility(widget: Widget) -> WidgetVisibility {
+    active_scene()
+        .await
+        .map(|scene| scene.visibility(widget))
+        .unwrap_or_default()
+}
 
-    let mut id = String::with_capacity(12);
-    for _ in 0..12 {
-        id.push(BASE62[thread_rng().gen_range(0..BASE62.len())] as char);
+// Switches every overlay to a scene, the countdown and sponsor toggles follow the scene
+async fn take_scene(id: &str) -> Result<(), StatusCode> {
+    let scenes = SCENES.lock().await;
+    let scene = scenes
+        .iter()
+        .find(|s| s.id == id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!("SCENE: took {}", scene.title);
+    // The scene still goes on air when it can't be saved, only the next start would miss it
+    let saved = save_scenes(&scenes, id).await;
+    drop(scenes);
+    *ACTIVE_SCENE.lock().await = scene.id.clone();
+
+    let shown = |widget| match scene.visibility(widget) {
+        WidgetVisibility::Hidden => Some(false),
+        WidgetVisibility::Auto => None,
+        WidgetVisibility::Shown => Some(true),
+    };
+    if let Some(show) = shown(Widget::Countdown) {
+        *SHOW_COUNTDOWN.lock().await = show;
+    }
+    if let Some(show) = shown(Widget::Sponsors) {
+        *SHOW_SPONSOR.lock().await = show;
+        SPONSOR_ROLL_WAKE.notify_one();
     }
 
-    std::fs::create_dir_all(Path::new(&format!("./teams/{}", id))).unwrap();
+    saved
+}
 
-    // Loops through the fields of the form
-    while let Some(field) = payload.next_field().await.unwrap() {
-        // Gets the name and data of the field
-        let name = field.name().unwrap().to_string();
-        let data = field.bytes().await.unwrap();
+// Handles and returns the css of the active scene, every overlay polls it
+async fn scene_css_handler() -> Html<String> {
+    let Some(scene) = active_scene().await else {
+        return Html(String::new());
+    };
 
-        if name == "home.png" || name == "away.png" {
-            // Writes the data to a .png file
-            println!(" -> LOGO: recieved {}\n\tLENGTH: {}", name, data.len());
-            tokio::fs::write(Path::new(&format!("./teams/{}/{}", id, name)), data)
-                .await
-                .unwrap();
-        } else if name == "home_name" {
-            home_name = std::str::from_utf8(&data).unwrap().to_string();
-        } else if name == "away_name" {
-            away_name = std::str::from_utf8(&data).unwrap().to_string();
-        } else if name == "home_color" {
-            home_color = std::str::from_utf8(&data).unwrap().to_string();
-        } else if name == "away_color" {
-            away_color = std::str::from_utf8(&data).unwrap().to_string();
-        }
+    let css: String = scene
+        .widgets
+        .iter()
+        .map(|(widget, layout)| layout.css(*widget))
+        .collect();
+    Html(format!("<style> {}</style>", css))
+}
+
+// Handles and returns the scene buttons of the dashboard
+async fn scene_buttons_handler() -> Html<String> {
+    let active = ACTIVE_SCENE.lock().await.clone();
+    let mut html = String::new();
+
+    for scene in SCENES.lock().await.iter() {
+        html += &format!(
+            "<button class=\"scene-button{}\" hx-post=\"/scenes/{}/take\" hx-target=\"#scene-buttons\">{}</button>",
+            if scene.id == active { " active" } else { "" },
+            scene.id,
+            html_escape(&scene.title),
+        );
     }
 
-    let info_container = TeamInfoContainer {
-        home_name: home_name,
-        home_color: home_color,
-        away_name: away_name,
-        away_color: away_color,
-    };
+    Html(html)
+}
 
-    dbg!(&info_container);
+// Handles and returns the scenes as options for the countdown page
+async fn scene_options_handler() -> Html<String> {
+    let mut html = String::new();
+    for scene in SCENES.lock().await.iter() {
+        html += &format!(
+            "<option value=\"{}\">{}</option>",
+            scene.id,
+            html_escape(&scene.title)
+        );
+    }
+    Html(html)
+}
 
-    let json = serde_json::to_string(&info_container).expect("Failed to serialize team info");
-    tokio::fs::write(Path::new(&format!("./teams/{}/teaminfo.json", id)), json)
-        .await
-        .expect("Failed to write to team info");
+async fn take_scene_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    take_scene(&id).await?;
+    Ok(scene_buttons_handler().await)
+}
 
-    StatusCode::OK
+// A scene as listed by the API
+#[derive(Serialize)]
+struct SceneApiInfo {
+    #[serde(flatten)]
+    scene: Scene,
+    active: bool,
 }
 
-async fn delete_preset_handler(axum::extract::Path(id): axum::extract::Path<String>) {
-    let id_path = format!("teams/{}", id);
-    if std::path::Path::new(&id_path).is_dir() {
-        println!(" -> REMOVE: {}", id);
-        tokio::fs::remove_dir_all(id_path)
+async fn list_scenes_handler() -> Json<Vec<SceneApiInfo>> {
+    let active = ACTIVE_SCENE.lock().await.clone();
+    Json(
+        SCENES
+            .lock()
             .await
-            .expect("Could not delete id!");
-    } else {
-        println!(" -> FAIL: cannot delete {}, doesnt exist!", id);
+            .iter()
+            .map(|scene| SceneApiInfo {
+                active: scene.id == active,
+                scene: scene.clone(),
+            })
+            .collect(),
+    )
+}
+
+async fn put_scene_handler(
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(mut scene): Json<Scene>,
+) -> StatusCode {
+    scene.id = id;
+    if !scene.valid() {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    info!("SCENE: saved {}", scene.title);
+    let active = ACTIVE_SCENE.lock().await.clone();
+    let mut scenes = SCENES.lock().await;
+    match scenes.iter_mut().find(|s| s.id == scene.id) {
+        Some(existing) => *existing = scene,
+        None => scenes.push(scene),
+    }
+    match save_scenes(&scenes, &active).await {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
     }
 }
 
-// endregion: --- File upload handlers
-// region: --- Sponsor roll
+async fn delete_scene_handler(axum::extract::Path(id): axum::extract::Path<String>) -> StatusCode {
+    let active = ACTIVE_SCENE.lock().await.clone();
+    let mut scenes = SCENES.lock().await;
+    let Some(index) = scenes.iter().position(|s| s.id == id) else {
+        return StatusCode::NOT_FOUND;
+    };
 
-async fn load_sponsors() -> Vec<Html<String>> {
-    let mut entries = tokio::fs::read_dir("./sponsors").await.unwrap();
-    let mut sponsor_imgs: Vec<tokio::fs::DirEntry> = Vec::new();
+    // The overlay always has a scene
+    if id == active {
+        return StatusCode::CONFLICT;
+    }
 
-    while let Ok(Some(res)) = entries.next_entry().await {
-        let entry = res;
-        if let Some(extension) = entry.path().extension() {
-            if extension == "png" {
-                sponsor_imgs.push(entry);
-            }
-        }
+    info!("SCENE: deleted {}", id);
+    scenes.remove(index);
+    match save_scenes(&scenes, &active).await {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
     }
+}
 
-    let mut img_tags: Vec<Html<String>> = Vec::new();
+// endregion: --- Scenes
+// region: --- Themes
 
-    for i in 0..sponsor_imgs.len() {
-        let img_bytes = tokio::fs::read(sponsor_imgs[i].path()).await.unwrap();
+// Theme names are folder names in ./templates
+fn valid_theme_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
 
-        img_tags.push(Html(format!(
-            "<img src=\"data:image/png;base64,{}\" width=\"10%\" height=\"10%\" id=\"sponsor_roll_img\"/>",
-            BASE64_STANDARD.encode(&img_bytes)
-        )));
-    }
+// Templates are html or css files directly in the theme folder
+fn valid_template_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && (name.ends_with(".html") || name.ends_with(".css"))
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
 
-    return img_tags;
+// The templates compiled into the binary, a theme can replace them
+fn builtin_template(name: &str) -> Option<&'static str> {
+    match name {
+        "overlay.html" => Some(include_str!("html/scoreboard/overlay.html")),
+        "theme.css" => Some(""),
+        _ => None,
+    }
 }
 
-async fn sponsor_roll_ticker() {
-    if SPONSOR_IMG_TAGS.lock().await.len() != 0 {
-        loop {
-            if SPONSOR_IMG_TAGS.lock().await.len() > 1 && *SHOW_SPONSOR.lock().await {
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                let mut last_sponsor = LAST_SPONSOR.lock().await;
+// Builds a template environment for a theme, the theme's files are read on every render so edits show up right away
+fn theme_environment(theme: String) -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_loader(move |name| {
+        if !valid_template_name(name) {
+            return Ok(None);
+        }
 
-                if *last_sponsor + 1 > SPONSOR_IMG_TAGS.lock().await.len() - 1 {
-                    *last_sponsor = 0;
-                } else {
-                    *last_sponsor = *last_sponsor + 1;
-                }
+        match std::fs::read_to_string(format!("{}/{}/{}", TEMPLATES_DIR, theme, name)) {
+            Ok(source) => Ok(Some(source)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(builtin_template(name).map(String::from))
             }
+            Err(err) => Err(minijinja::Error::new(
+                minijinja::ErrorKind::InvalidOperation,
+                format!("could not read {}: {}", name, err),
+            )),
         }
-    }
+    });
+    env
 }
 
-async fn sponsor_roll_handler() -> Html<String> {
-    if SPONSOR_IMG_TAGS.lock().await.len() != 0 {
-        let sponsor_imgs = SPONSOR_IMG_TAGS.lock().await;
-        let last_sponsor = LAST_SPONSOR.lock().await;
+// A team as templates see it
+#[derive(Serialize)]
+struct TemplateTeam {
+    name: String,
+    points: i32,
+    logo: String,
+    primary: String,
+    secondary: String,
+    text: String,
+}
+
+// The game state templates are rendered with
+#[derive(Serialize)]
+struct TemplateState {
+    theme: String,
+    home: TemplateTeam,
+    away: TemplateTeam,
+    clock: String,
+    quarter: u8,
+    period: String,
+    show_quarter: bool,
+    countdown_title: String,
+    countdown: String,
+    show_countdown: bool,
+    scene: String,
+    popup: Option<String>,
+    lower_third: Option<LowerThird>,
+}
 
-        return sponsor_imgs[*last_sponsor].clone();
+async fn template_state() -> TemplateState {
+    let team = |name: String, points: i32, logo, colors: TeamColors| TemplateTeam {
+        name,
+        points,
+        logo,
+        primary: hex_color(colors.primary),
+        secondary: hex_color(colors.secondary),
+        text: hex_color(contrast_text_color(colors.primary)),
+    };
+    let quarter = *QUARTER.lock().await;
+    let (countdown_title, countdown) = active_countdown_display().await;
+
+    TemplateState {
+        theme: THEME.lock().await.clone(),
+        home: team(
+            HOME_NAME.lock().await.clone(),
+            *HOME_POINTS.lock().await,
+            image_url("home")
+                .await
+                .unwrap_or_else(|| String::from("/home_png")),
+            *HOME_COLORS.lock().await,
+        ),
+        away: team(
+            AWAY_NAME.lock().await.clone(),
+            *AWAY_POINTS.lock().await,
+            image_url("away")
+                .await
+                .unwrap_or_else(|| String::from("/away_png")),
+            *AWAY_COLORS.lock().await,
+        ),
+        clock: format!("{}:{:02}", *TIME_MINS.lock().await, *TIME_SECS.lock().await),
+        quarter,
+        period: period_label(quarter),
+        show_quarter: *SHOW_QUARTER.lock().await,
+        countdown_title,
+        countdown,
+        show_countdown: *SHOW_COUNTDOWN.lock().await,
+        scene: ACTIVE_SCENE.lock().await.clone(),
+        popup: CURRENT_POPUP
+            .lock()
+            .await
+            .as_ref()
+            .map(|popup| popup.text.clone()),
+        lower_third: ON_AIR_LOWER_THIRD
+            .lock()
+            .await
+            .as_ref()
+            .map(|on_air| on_air.lower_third.clone()),
     }
-    return Html(String::new());
 }
 
-async fn show_sponsor_roll_handler() {
-    let mut show_sponsor = SHOW_SPONSOR.lock().await;
+// Renders a template of the active theme, Ok(None) if neither the theme nor froggi has it
+async fn render_template(name: String) -> Result<Option<String>, String> {
+    let theme = THEME.lock().await.clone();
+    let state = template_state().await;
+
+    tokio::task::spawn_blocking(move || {
+        let env = theme_environment(theme);
+        match env.get_template(&name) {
+            Ok(template) => template.render(state).map(Some),
+            Err(err) if err.kind() == minijinja::ErrorKind::TemplateNotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    })
+    .await
+    .unwrap()
+    .map_err(|err| {
+        warn!("TEMPLATE: {}", err);
+        // The alternate format includes the lines around the error
+        format!("{:#}", err)
+    })
+}
 
-    if *show_sponsor {
-        *show_sponsor = false;
-    } else {
-        *show_sponsor = true;
-    }
+// Shown instead of the overlay when one of its templates is broken, so it doesn't go blank without a reason
+fn template_error_page(theme: &str, err: &str) -> Response<Body> {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Html(format!(
+            "<!DOCTYPE html><html><head><title>Froggi template error</title></head><body style=\"background: white; color: black; font-family: monospace; padding: 20px;\"><h1>Template error in the {} theme</h1><pre>{}</pre><p>Fix the template in {}/{} and reload the overlay.</p></body></html>",
+            html_escape(theme),
+            html_escape(err),
+            TEMPLATES_DIR,
+            html_escape(theme),
+        )),
+    )
+        .into_response()
 }
 
-async fn sponsor_roll_css_handler() -> Html<&'static str> {
-    let show_sponsor = SHOW_SPONSOR.lock().await;
+// Handles and returns the css of the active theme
+async fn theme_css_handler() -> impl IntoResponse {
+    let css = match render_template(String::from("theme.css")).await {
+        Ok(css) => css.unwrap_or_default(),
+        Err(err) => format!("/* {} */", err.replace("*/", "* /")),
+    };
 
-    if *show_sponsor {
-        return Html("<style> #show-sponsor { background-color: rgb(227, 45, 32); } </style>");
-    } else {
-        return Html("<style> #sponsor_roll_img { display: none; } #show-sponsor { background-color: #e9981f; } </style>");
+    Response::builder()
+        .header(CONTENT_TYPE, TEXT_CSS.to_string())
+        .body(Body::from(css))
+        .unwrap()
+}
+
+// Renders any other template of the theme with the current game state, themes poll these to update the overlay
+async fn theme_template_handler(
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    if !valid_template_name(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    match render_template(name).await {
+        Ok(Some(html)) => Ok(Html(html)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        // Swapped into the overlay where the template would have been
+        Err(err) => Ok(Html(format!(
+            "<pre class=\"template-error\">{}</pre>",
+            html_escape(&err)
+        ))),
     }
 }
 
-// endregion: --- Sponsor roll
-// region: --- Countdown
+// A template of the active theme and why it is broken, if it is
+#[derive(Serialize)]
+struct TemplateInfo {
+    name: String,
+    error: Option<String>,
+}
 
-async fn countdown_ticker() {
-    loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        let mut countdown_started = COUNTDOWN_STARTED.lock().await;
-        if *countdown_started {
-            let mut countdown_mins = COUNTDOWN_MINS.lock().await;
-            let mut countdown_secs = COUNTDOWN_SECS.lock().await;
-            if *countdown_secs == 0 {
-                if *countdown_mins == 0 {
-                    *countdown_started = false;
-                } else {
-                    *countdown_mins -= 1;
-                    *countdown_secs = 59;
-                }
-            } else {
-                *countdown_secs -= 1;
+#[derive(Serialize)]
+struct ThemeInfo {
+    theme: String,
+    templates: Vec<TemplateInfo>,
+}
+
+// Checks every template in the theme folder
+async fn check_theme() -> ThemeInfo {
+    let theme = THEME.lock().await.clone();
+    let mut names = Vec::new();
+    if let Ok(mut dir) = tokio::fs::read_dir(format!("{}/{}", TEMPLATES_DIR, theme)).await {
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if valid_template_name(&name) {
+                names.push(name);
             }
         }
     }
+    names.sort();
+
+    // Templates are rendered with the current game state too, unknown filters and the like only fail then
+    let env_theme = theme.clone();
+    let state = template_state().await;
+    let templates = tokio::task::spawn_blocking(move || {
+        let env = theme_environment(env_theme);
+        names
+            .into_iter()
+            .map(|name| TemplateInfo {
+                error: env
+                    .get_template(&name)
+                    .and_then(|template| template.render(&state))
+                    .err()
+                    .map(|err| format!("{:#}", err)),
+                name,
+            })
+            .collect()
+    })
+    .await
+    .unwrap();
+
+    ThemeInfo { theme, templates }
 }
 
-async fn start_countdown_handler() {
-    *COUNTDOWN_STARTED.lock().await = true;
-}
+// Logs what is wrong with the theme from the config when froggi starts
+async fn load_theme() {
+    let info = check_theme().await;
+    let dir = format!("{}/{}", TEMPLATES_DIR, info.theme);
 
-async fn stop_countdown_handler() {
-    *COUNTDOWN_STARTED.lock().await = false;
+    if info.theme != "default" && !Path::new(&dir).is_dir() {
+        warn!(
+            "TEMPLATE: theme {} has no folder {}, using the built in overlay",
+            info.theme, dir
+        );
+        return;
+    }
+
+    for template in &info.templates {
+        match &template.error {
+            Some(err) => warn!("TEMPLATE: {}/{} is broken: {}", dir, template.name, err),
+            None => info!("TEMPLATE: using {}/{}", dir, template.name),
+        }
+    }
 }
 
-async fn countdown_display_handler() -> Html<String> {
-    Html(format!(
-        "<h2 style=\"font-family: monospace;\">{}</h2> <br>
-         <p id=\"countdown-display-clock\" style=\"font-family: monospace; font-size: 150%;\">{}:{:02?}</p>
-    ",
-        *COUNTDOWN_TITLE.lock().await,
-        *COUNTDOWN_MINS.lock().await,
-        *COUNTDOWN_SECS.lock().await
-    ))
+async fn theme_info_handler() -> Json<ThemeInfo> {
+    Json(check_theme().await)
 }
 
-async fn dashboard_countdown_display_handler() -> Html<String> {
-    Html(format!(
-        "{}:{:02?}",
-        *COUNTDOWN_MINS.lock().await,
-        *COUNTDOWN_SECS.lock().await
-    ))
+// endregion: --- Themes
+// region: --- Scorebug render
+
+// The size of a rendered scorebug, ?width=&height= overrides render_width and render_height from the config
+#[derive(Deserialize)]
+struct RenderQuery {
+    width: Option<u32>,
+    height: Option<u32>,
 }
 
-async fn show_countdown_handler() {
-    let mut show_countdown = SHOW_COUNTDOWN.lock().await;
-    if *show_countdown {
-        *show_countdown = false;
-    } else {
-        *show_countdown = true;
+// Picks the size of a rendered scorebug, sizes that are too small or too big to render are refused
+async fn render_size(query: &RenderQuery) -> Result<(u32, u32), StatusCode> {
+    let width = match query.width {
+        Some(width) => width,
+        None => *RENDER_WIDTH.lock().await,
+    };
+    let height = match query.height {
+        Some(height) => height,
+        None => *RENDER_HEIGHT.lock().await,
+    };
+
+    if !(100..=RENDER_MAX_WIDTH).contains(&width) || !(20..=RENDER_MAX_HEIGHT).contains(&height) {
+        return Err(StatusCode::BAD_REQUEST);
     }
-}
 
-async fn countdown_css_handler() -> Html<&'static str> {
-    if *SHOW_COUNTDOWN.lock().await {
-        return Html("<style> .white-boxes-container { display: none; } #show-countdown { background-color: rgb(227, 45, 32); } </style>");
-    } else {
-        return Html("<style> .white-boxes-container { display: flex; } #show-countdown { background-color: #e9981f; } #countdown { display: none; }</style>");
+    // Too narrow for its height, the boxes would be less than a pixel wide
+    if scorebug_unit(width, height) < 1.0 {
+        return Err(StatusCode::BAD_REQUEST);
     }
+
+    Ok((width, height))
 }
 
-async fn quick_countdown_handler(
-    axum::extract::Path((mins, secs)): axum::extract::Path<(u32, u32)>,
-) {
-    *COUNTDOWN_MINS.lock().await = mins;
-    *COUNTDOWN_SECS.lock().await = secs;
+// Width of a fiftieth of the room left for the boxes of the scorebug once the gaps between them are taken out
+fn scorebug_unit(width: u32, height: u32) -> f64 {
+    (width as f64 - height as f64 * RENDER_GAP * 5.0) / 50.0
 }
 
-async fn countdown_mins_up_handler() {
-    let mut countdown_mins = COUNTDOWN_MINS.lock().await;
-    *countdown_mins = *countdown_mins + 1;
+// Embeds a logo in the scorebug as a data uri, so the rendered image doesn't depend on froggi serving it
+fn logo_data_uri(logo: &[u8]) -> Option<String> {
+    let kind = ImageKind::detect(logo)?;
+    Some(format!(
+        "data:{};base64,{}",
+        kind.content_type(),
+        BASE64_STANDARD.encode(logo)
+    ))
 }
 
-async fn countdown_mins_down_handler() {
-    let mut countdown_mins = COUNTDOWN_MINS.lock().await;
-    if *countdown_mins > 0 {
-        *countdown_mins = *countdown_mins - 1;
+// Draws the scorebug of the overlay as an svg with the same game state: the teams with their logos and colors, the score and the clock
+async fn scorebug_svg(width: u32, height: u32) -> String {
+    let home_name = HOME_NAME.lock().await.clone();
+    let away_name = AWAY_NAME.lock().await.clone();
+    let home_points = *HOME_POINTS.lock().await;
+    let away_points = *AWAY_POINTS.lock().await;
+    let home_colors = *HOME_COLORS.lock().await;
+    let away_colors = *AWAY_COLORS.lock().await;
+    let home_logo = logo_data_uri(&HOME_IMG_DATA.lock().await);
+    let away_logo = logo_data_uri(&AWAY_IMG_DATA.lock().await);
+    let quarter = *QUARTER.lock().await;
+    let mut clock = format!("{}:{:02}", *TIME_MINS.lock().await, *TIME_SECS.lock().await);
+    if *SHOW_QUARTER.lock().await {
+        clock += &format!(" - {}", period_label(quarter));
     }
+
+    // The boxes keep the proportions of the overlay: team, score, team, clock
+    let unit = scorebug_unit(width, height);
+    let (width, height) = (width as f64, height as f64);
+    let gap = height * RENDER_GAP;
+    let box_height = height - gap * 2.0;
+    let stripe = box_height * 0.1;
+    let font_size = box_height * 0.5;
+    let (team_width, score_width, clock_width) = (16.0 * unit, 8.0 * unit, 10.0 * unit);
+    let text_y = gap + (box_height - stripe) / 2.0;
+
+    // Nested svg elements clip their contents, so long names can't run into the next box
+    let team_box = |x: f64, name: &str, logo: &Option<String>, colors: TeamColors| {
+        let logo_size = box_height - stripe - gap;
+        let (logo, text_x) = match logo {
+            Some(uri) => (
+                format!(
+                    "<image x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" href=\"{}\"/>",
+                    gap / 2.0,
+                    gap / 2.0,
+                    logo_size,
+                    logo_size,
+                    uri
+                ),
+                logo_size + gap,
+            ),
+            None => (String::new(), gap),
+        };
+        format!(
+            "<svg x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><rect width=\"100%\" height=\"100%\" fill=\"{}\"/><rect y=\"{:.1}\" width=\"100%\" height=\"{:.1}\" fill=\"{}\"/>{}<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{}\" dominant-baseline=\"central\">{}</text></svg>",
+            x,
+            gap,
+            team_width,
+            box_height,
+            hex_color(colors.primary),
+            box_height - stripe,
+            stripe,
+            hex_color(colors.secondary),
+            logo,
+            text_x,
+            text_y - gap,
+            hex_color(contrast_text_color(colors.primary)),
+            html_escape(name),
+        )
+    };
+    let centered_text = |x: f64, text: &str, fill: String| {
+        format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+            x,
+            text_y,
+            fill,
+            html_escape(text)
+        )
+    };
+
+    let home_x = gap;
+    let score_x = home_x + team_width + gap;
+    let away_x = score_x + score_width + gap;
+    let clock_x = away_x + team_width + gap;
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-weight=\"bold\" font-size=\"{fs:.1}\">{home}<rect x=\"{sx:.1}\" y=\"{g:.1}\" width=\"{half:.1}\" height=\"{bh:.1}\" fill=\"{hp}\"/><rect x=\"{sx2:.1}\" y=\"{g:.1}\" width=\"{half:.1}\" height=\"{bh:.1}\" fill=\"{ap}\"/>{hs}{as_}{away}<rect x=\"{cx:.1}\" y=\"{g:.1}\" width=\"{cw:.1}\" height=\"{bh:.1}\" fill=\"#ffffff\"/>{clock}</svg>",
+        w = width,
+        h = height,
+        fs = font_size,
+        home = team_box(home_x, &home_name, &home_logo, home_colors),
+        sx = score_x,
+        sx2 = score_x + score_width / 2.0,
+        g = gap,
+        half = score_width / 2.0,
+        bh = box_height,
+        hp = hex_color(home_colors.primary),
+        ap = hex_color(away_colors.primary),
+        hs = centered_text(
+            score_x + score_width / 4.0,
+            &home_points.to_string(),
+            hex_color(contrast_text_color(home_colors.primary))
+        ),
+        as_ = centered_text(
+            score_x + score_width * 3.0 / 4.0,
+            &away_points.to_string(),
+            hex_color(contrast_text_color(away_colors.primary))
+        ),
+        away = team_box(away_x, &away_name, &away_logo, away_colors),
+        cx = clock_x,
+        cw = clock_width,
+        clock = centered_text(clock_x + clock_width / 2.0, &clock, String::from("#000000")),
+    )
 }
 
-async fn countdown_secs_up_handler() {
-    let mut countdown_secs = COUNTDOWN_SECS.lock().await;
-    if *countdown_secs < 59 {
-        *countdown_secs = *countdown_secs + 1;
-    } else {
-        let mut countdown_mins = COUNTDOWN_MINS.lock().await;
-        *countdown_mins = *countdown_mins + 1;
-        *countdown_secs = 0;
+// Loads the fonts rendered scorebugs are drawn with, render_font from the config is used instead of the system's sans-serif font
+async fn load_render_fonts() {
+    let font = RENDER_FONT.lock().await.clone();
+
+    let fonts = tokio::task::spawn_blocking(move || {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_system_fonts();
+
+        if let Some(font) = font {
+            match std::fs::read(&font) {
+                Ok(data) => {
+                    fonts.load_font_data(data);
+                    match fonts.faces().last().and_then(|face| face.families.first()) {
+                        Some((family, _)) => {
+                            info!("RENDER: using {} from {}", family, font);
+                            fonts.set_sans_serif_family(family.clone());
+                        }
+                        None => warn!("RENDER: {} is not a font", font),
+                    }
+                }
+                Err(err) => warn!("RENDER: could not read render_font {}: {}", font, err),
+            }
+        }
+
+        // fontdb picks Arial for sans-serif, which most linux systems don't have
+        let sans_serif = fontdb::Query {
+            families: &[fontdb::Family::SansSerif],
+            ..Default::default()
+        };
+        if fonts.query(&sans_serif).is_none() {
+            let fallback = ["DejaVu Sans", "Liberation Sans", "Noto Sans", "Helvetica"]
+                .into_iter()
+                .map(String::from)
+                .find(|family| {
+                    fonts.faces().any(|face| face.families.iter().any(|(f, _)| f == family))
+                })
+                .or_else(|| {
+                    fonts
+                        .faces()
+                        .next()
+                        .and_then(|face| face.families.first())
+                        .map(|(family, _)| family.clone())
+                });
+            if let Some(family) = fallback {
+                fonts.set_sans_serif_family(family);
+            }
+        }
+
+        if fonts.is_empty() {
+            warn!("RENDER: no fonts found, rendered scorebugs will have no text. Set render_font to a font file");
+        }
+        fonts
+    })
+    .await
+    .unwrap();
+
+    *RENDER_FONTS.lock().await = Arc::new(fonts);
+}
+
+// Handles and returns the scorebug as an svg
+async fn scorebug_svg_handler(
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    let (width, height) = render_size(&query).await?;
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "image/svg+xml")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(scorebug_svg(width, height).await))
+        .unwrap())
+}
+
+// Handles and returns the scorebug rasterized to a png, for tools that can't show a browser source
+async fn scorebug_png_handler(
+    axum::extract::Query(query): axum::extract::Query<RenderQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    let (width, height) = render_size(&query).await?;
+    let svg = scorebug_svg(width, height).await;
+
+    // Only a few renders run at once, and the cache is checked once it is this request's turn,
+    // so clients polling the same state and size share one render
+    let _permit = RENDER_PERMITS.acquire().await.unwrap();
+    if let Some((cached_svg, png)) = RENDER_CACHE.lock().await.as_ref() {
+        if *cached_svg == svg {
+            return Ok(scorebug_png_response(png.clone()));
+        }
     }
+
+    let fonts = RENDER_FONTS.lock().await.clone();
+    let (svg, png) = tokio::task::spawn_blocking(move || {
+        let options = usvg::Options {
+            fontdb: fonts,
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_str(&svg, &options).map_err(|err| err.to_string())?;
+        let mut pixmap =
+            tiny_skia::Pixmap::new(width, height).ok_or_else(|| String::from("invalid size"))?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        let png = pixmap.encode_png().map_err(|err| err.to_string())?;
+        Ok::<_, String>((svg, axum::body::Bytes::from(png)))
+    })
+    .await
+    .unwrap()
+    .map_err(|err| {
+        warn!("FAIL: rendering the scorebug: {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    *RENDER_CACHE.lock().await = Some((svg, png.clone()));
+    Ok(scorebug_png_response(png))
 }
 
-async fn countdown_secs_down_handler() {
-    let mut countdown_secs = COUNTDOWN_SECS.lock().await;
-    let mut countdown_mins = COUNTDOWN_MINS.lock().await;
-    if *countdown_secs > 0 {
-        *countdown_secs = *countdown_secs - 1;
-    } else if *countdown_mins - 1 > 0 {
-        *countdown_mins = *countdown_mins - 1;
-        *countdown_secs = 59;
+fn scorebug_png_response(png: axum::body::Bytes) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "image/png")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(png))
+        .unwrap()
+}
+
+// endregion: --- Scorebug render
+// region: --- Overlay background
+
+// Lets one overlay ask for its own background, ?transparent=1 or ?background=<color>
+#[derive(Deserialize, Default)]
+struct OverlayBackgroundQuery {
+    transparent: Option<String>,
+    background: Option<String>,
+}
+
+// Handles and returns the overlay background as css, a chromakey color or transparent for browser sources with alpha
+async fn chromargb_handler(headers: HeaderMap) -> Html<String> {
+    // htmx sends the url of the overlay page, which has the query of the overlay
+    let query = headers
+        .get("HX-Current-URL")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|url| url.parse::<Uri>().ok())
+        .and_then(|uri| axum::extract::Query::<OverlayBackgroundQuery>::try_from_uri(&uri).ok())
+        .map(|axum::extract::Query(query)| query)
+        .unwrap_or_default();
+
+    let transparent = match query.transparent.as_deref() {
+        Some(transparent) => matches!(transparent, "1" | "true"),
+        None => *TRANSPARENT_OVERLAY.lock().await,
+    };
+    if transparent {
+        return Html(String::from(
+            "<style>html, body { background: transparent !important; }</style>",
+        ));
     }
+
+    let (r, g, b) = match query.background.as_deref().and_then(parse_color) {
+        Some(color) => color,
+        None => *CHROMAKEY.lock().await,
+    };
+    Html(format!(
+        "<style>html, body {{ background-color: rgb({}, {}, {}) !important; }}</style>",
+        r, g, b
+    ))
+}
+
+// Handles and returns the overlay background settings of the dashboard
+async fn overlay_background_form_handler() -> Html<String> {
+    Html(format!(
+        "
+        <label for=\"chromakey\">Chromakey:</label>
+        <input type=\"color\" name=\"chromakey\" id=\"chromakey\" value=\"{}\">
+        <label><input type=\"checkbox\" name=\"transparent\"{}> Transparent</label>
+        <input type=\"submit\" value=\"Save\">
+        ",
+        hex_color(*CHROMAKEY.lock().await),
+        if *TRANSPARENT_OVERLAY.lock().await {
+            " checked"
+        } else {
+            ""
+        },
+    ))
 }
 
 #[derive(Deserialize)]
-struct CountdownTitle {
-    title: String,
+struct OverlayBackgroundForm {
+    chromakey: String,
+    transparent: Option<String>,
 }
 
-async fn countdown_title_handler(Form(title_data): Form<CountdownTitle>) -> impl IntoResponse {
-    println!(" -> COUNTDOWN: title set to {}", title_data.title);
-    *COUNTDOWN_TITLE.lock().await = title_data.title;
-    Redirect::to("/countdown")
+// Changes the background of every overlay that doesn't ask for its own, and saves it in the config
+async fn overlay_background_handler(Form(form): Form<OverlayBackgroundForm>) -> StatusCode {
+    let Some((r, g, b)) = parse_color(&form.chromakey) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let transparent = form.transparent.is_some();
+
+    info!(
+        "OVERLAY: background set to {}",
+        if transparent {
+            String::from("transparent")
+        } else {
+            hex_color((r, g, b))
+        }
+    );
+    *CHROMAKEY.lock().await = (r, g, b);
+    *TRANSPARENT_OVERLAY.lock().await = transparent;
+
+    if let Err(status) = set_config_value("chromakey", &format!("{}, {}, {}", r, g, b)).await {
+        return status;
+    }
+    match set_config_value("transparent_overlay", &transparent.to_string()).await {
+        Ok(()) => StatusCode::OK,
+        Err(status) => status,
+    }
 }
 
-// endregion: --- Countdown
+// endregion: --- Overlay background
 // region: --- Login fn's
 
 #[derive(Deserialize)]
 struct LoginInfo {
     username: String,
-    password: String,
+    password: Redacted<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct AuthClaims {
     sub: String,
     un: String,
+    csrf: String,
+    iat: usize,
     exp: usize,
 }
 
 async fn create_login_handler(Form(login): Form<LoginInfo>) -> impl IntoResponse {
     match tokio::fs::File::open("login/logins.txt").await {
         Ok(_) => {
-            println!(" -> BLOCK: password already exists, cannot create new one");
-            return Redirect::to("/login");
+            warn!("BLOCK: password already exists, cannot create new one");
+            Redirect::to("/login")
         }
         Err(_) => {
             let salt = SaltString::generate(&mut rand::rngs::OsRng);
             let argon2 = Argon2::default();
 
-            let pw_hash = argon2
-                .hash_password(login.password.as_bytes(), &salt)
-                .unwrap()
-                .to_string();
+            let pw_hash = Redacted(
+                argon2
+                    .hash_password(login.password.expose().as_bytes(), &salt)
+                    .unwrap()
+                    .to_string(),
+            );
+
+            info!("WRITE: login info for {} to logins.txt", login.username);
+
+            let mut logins_txt = tokio::fs::File::create("login/logins.txt").await.unwrap();
+            logins_txt
+                .write_all(format!("{}\n{}", login.username, pw_hash.expose()).as_bytes())
+                .await
+                .unwrap();
+            Redirect::to("/login")
+        }
+    }
+}
+
+// Tracks failed login attempts for a single client address or username
+struct LoginAttempts {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+    // Set while a password is being checked, so concurrent attempts can't all get past the throttle
+    verifying_since: Option<Instant>,
+}
+
+impl LoginAttempts {
+    fn new(now: Instant) -> LoginAttempts {
+        LoginAttempts {
+            failures: 0,
+            last_failure: now,
+            locked_until: None,
+            verifying_since: None,
+        }
+    }
+
+    fn is_verifying(&self, now: Instant) -> bool {
+        self.verifying_since.is_some_and(|since| {
+            now.duration_since(since) < Duration::from_secs(LOGIN_VERIFY_TIMEOUT_SECS)
+        })
+    }
+
+    // Returns how long the client has to wait before it may try to log in again
+    fn retry_after(&self, now: Instant) -> Option<Duration> {
+        if let Some(locked_until) = self.locked_until {
+            if locked_until > now {
+                return Some(locked_until - now);
+            }
+        }
+
+        // Only one password check at a time, the next attempt is counted once this one has failed
+        if self.is_verifying(now) {
+            return Some(Duration::from_secs(1));
+        }
+        if self.failures == 0 {
+            return None;
+        }
+
+        // Doubles the delay after every failure, starting at one second
+        let backoff = Duration::from_secs(
+            2u64.saturating_pow(self.failures.saturating_sub(1))
+                .min(LOGIN_BACKOFF_MAX_SECS),
+        );
+        let allowed_at = self.last_failure + backoff;
+
+        if allowed_at > now {
+            Some(allowed_at - now)
+        } else {
+            None
+        }
+    }
+
+    // Records a failed attempt, returns true if this failure triggered a lockout
+    fn record_failure(&mut self, now: Instant, max_failures: u32, lockout: Duration) -> bool {
+        self.failures += 1;
+        self.last_failure = now;
+        self.verifying_since = None;
+
+        if self.failures >= max_failures {
+            self.failures = 0;
+            self.locked_until = Some(now + lockout);
+            true
+        } else {
+            false
+        }
+    }
+
+    // An entry can be forgotten once it is neither locked nor recent enough to matter
+    fn is_stale(&self, now: Instant, lockout: Duration) -> bool {
+        self.locked_until.is_none_or(|until| until <= now)
+            && !self.is_verifying(now)
+            && now.duration_since(self.last_failure) > lockout
+    }
+}
+
+// Reserves a login attempt for the client address and the username before the password is checked,
+// returns how long the client has to wait instead if it may not try yet
+async fn start_login_attempt(ip: IpAddr, username: &str) -> Option<Duration> {
+    let now = Instant::now();
+    let lockout = Duration::from_secs(*LOGIN_LOCKOUT_SECS.lock().await);
+
+    let mut by_ip = LOGIN_ATTEMPTS_BY_IP.lock().await;
+    let mut by_user = LOGIN_ATTEMPTS_BY_USER.lock().await;
+    by_ip.retain(|_, attempts| !attempts.is_stale(now, lockout));
+    by_user.retain(|_, attempts| !attempts.is_stale(now, lockout));
+
+    let ip_wait = by_ip.get(&ip).and_then(|x| x.retry_after(now));
+    let user_wait = by_user.get(username).and_then(|x| x.retry_after(now));
+    if ip_wait.is_some() || user_wait.is_some() {
+        return ip_wait.max(user_wait);
+    }
+
+    // Both locks are held until here, so no other attempt can slip in between the check and the reservation
+    by_ip
+        .entry(ip)
+        .or_insert_with(|| LoginAttempts::new(now))
+        .verifying_since = Some(now);
+    by_user
+        .entry(username.to_string())
+        .or_insert_with(|| LoginAttempts::new(now))
+        .verifying_since = Some(now);
+
+    None
+}
+
+// Records a failed login for both the client address and the username
+async fn record_login_failure(ip: IpAddr, username: &str) {
+    let now = Instant::now();
+    let max_failures = *LOGIN_MAX_FAILURES.lock().await;
+    let lockout = Duration::from_secs(*LOGIN_LOCKOUT_SECS.lock().await);
+
+    let ip_locked = LOGIN_ATTEMPTS_BY_IP
+        .lock()
+        .await
+        .entry(ip)
+        .or_insert_with(|| LoginAttempts::new(now))
+        .record_failure(now, max_failures, lockout);
+    let user_locked = LOGIN_ATTEMPTS_BY_USER
+        .lock()
+        .await
+        .entry(username.to_string())
+        .or_insert_with(|| LoginAttempts::new(now))
+        .record_failure(now, max_failures, lockout);
 
-            println!(
-                " -> WRITE: login info to logins.txt\n\tINFO: un: {}, hash: {}, salt: {}",
-                login.username,
-                pw_hash,
-                salt.as_str()
-            );
+    warn!(target: SECURITY_LOG_TARGET, addr = %ip, username, "LOGIN: failed");
 
-            let mut logins_txt = tokio::fs::File::create("login/logins.txt").await.unwrap();
-            logins_txt
-                .write(format!("{}\n{}", login.username, pw_hash).as_bytes())
-                .await
-                .unwrap();
-            return Redirect::to("/login");
-        }
+    if ip_locked {
+        warn!(target: SECURITY_LOG_TARGET, addr = %ip, lockout_secs = lockout.as_secs(), "LOCKOUT: address");
     }
+    if user_locked {
+        warn!(target: SECURITY_LOG_TARGET, addr = %ip, username, lockout_secs = lockout.as_secs(), "LOCKOUT: username");
+    }
+}
+
+// Forgets previous failures after a successful login, which also ends the reserved attempt
+async fn clear_login_failures(ip: IpAddr, username: &str) {
+    LOGIN_ATTEMPTS_BY_IP.lock().await.remove(&ip);
+    LOGIN_ATTEMPTS_BY_USER.lock().await.remove(username);
 }
 
-async fn login_handler(Form(login): Form<LoginInfo>) -> impl IntoResponse {
-    println!(" -> ATTEMPT LOGIN");
+async fn login_handler(
+    ConnectInfo(client_addr): ConnectInfo<SocketAddr>,
+    Form(login): Form<LoginInfo>,
+) -> impl IntoResponse {
+    debug!("ATTEMPT LOGIN: {}", client_addr.ip());
+
+    // Throttles the client before doing any expensive password hashing
+    if let Some(wait) = start_login_attempt(client_addr.ip(), &login.username).await {
+        let wait_secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+
+        warn!(
+            target: SECURITY_LOG_TARGET,
+            addr = %client_addr.ip(),
+            username = login.username,
+            retry_after_secs = wait_secs,
+            "LOGIN: throttled"
+        );
+
+        let response = Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(RETRY_AFTER, wait_secs.to_string())
+            .header(CONTENT_TYPE, mime::TEXT_HTML_UTF_8.to_string())
+            .body(Body::from(format!(
+                "<h1>429 - Too many login attempts</h1><p>Try again in {} seconds.</p><a href=\"/login\">Back to login</a>",
+                wait_secs
+            )))
+            .unwrap();
+
+        return response.into_response();
+    }
+
     let pw_info: Vec<String> = tokio::fs::read_to_string("login/logins.txt")
         .await
         .unwrap()
         .split("\n")
         .map(|x| x.trim().to_string())
         .collect();
-    let parsed_hash = PasswordHash::new(&pw_info[1]).unwrap();
 
-    if login.username == pw_info[0]
-        && Argon2::default()
-            .verify_password(login.password.as_bytes(), &parsed_hash)
+    // Verifies the password on the blocking pool so hashing doesn't stall the runtime
+    let password = login.password.clone();
+    let pw_hash = Redacted(pw_info[1].clone());
+    let password_valid = tokio::task::spawn_blocking(move || {
+        let parsed_hash = PasswordHash::new(pw_hash.expose()).unwrap();
+        Argon2::default()
+            .verify_password(password.expose().as_bytes(), &parsed_hash)
             .is_ok()
-    {
-        println!(" -> LOGIN: successful");
-
-        let token_uuid = Uuid::new_v4().to_string();
-
-        let token_claim = AuthClaims {
-            sub: token_uuid,
-            un: login.username,
-            exp: (SystemTime::now() + std::time::Duration::from_secs(60 * 60 * 24))
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as usize,
-        };
-
-        let secret = tokio::fs::read_to_string("login/secrets.txt")
-            .await
-            .unwrap()
-            .trim()
-            .to_string();
-
-        let token = encode(
-            &Header::default(),
-            &token_claim,
-            &EncodingKey::from_secret(secret.as_bytes()),
+    })
+    .await
+    .unwrap();
+
+    if login.username == pw_info[0] && password_valid {
+        info!(target: SECURITY_LOG_TARGET, addr = %client_addr.ip(), username = login.username, "LOGIN: successful");
+        clear_login_failures(client_addr.ip(), &login.username).await;
+
+        let csrf_token = generate_csrf_token();
+        let auth_cookie = issue_auth_cookie(
+            Uuid::new_v4().to_string(),
+            login.username,
+            csrf_token.clone(),
         )
-        .unwrap();
-
-        let auth_cookie = Cookie::build(("authToken", token))
-            .http_only(true)
+        .await;
+        let csrf_cookie = Cookie::build((CSRF_COOKIE, csrf_token))
+            .same_site(SameSite::Strict)
             .secure(*SECURE_AUTH_COOKIE.lock().await)
-            .path("/");
+            .path("/")
+            .build();
 
         let response = Response::builder()
             .status(StatusCode::SEE_OTHER)
             .header("Location", "/")
             .header(SET_COOKIE, auth_cookie.to_string())
+            .header(SET_COOKIE, csrf_cookie.to_string())
             .body(axum::body::Body::empty())
             .unwrap();
 
-        return response.into_response();
+        response.into_response()
     } else {
-        println!(" -> LOGIN: failed");
+        record_login_failure(client_addr.ip(), &login.username).await;
 
         let response = Response::builder()
             .status(StatusCode::SEE_OTHER)
@@ -1213,32 +7986,250 @@ async fn login_handler(Form(login): Form<LoginInfo>) -> impl IntoResponse {
             .body(axum::body::Body::empty())
             .unwrap();
 
-        return response.into_response();
+        response.into_response()
+    }
+}
+
+// Returns the current unix time in seconds
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// Signs a new auth token for the session and wraps it in a cookie
+async fn issue_auth_cookie(session_id: String, username: String, csrf: String) -> Cookie<'static> {
+    let now = unix_now();
+
+    let token_claim = AuthClaims {
+        sub: session_id,
+        un: username,
+        csrf,
+        iat: now as usize,
+        exp: (now + *SESSION_LIFETIME_SECS.lock().await) as usize,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &token_claim,
+        &EncodingKey::from_secret(SECRET.lock().await.expose()),
+    )
+    .unwrap();
+
+    Cookie::build(("authToken", token))
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(*SECURE_AUTH_COOKIE.lock().await)
+        .path("/")
+        .build()
+}
+
+// Returns the claims of the auth cookie if it is present and valid
+async fn verify_auth_cookie(cookies: &CookieJar) -> Option<AuthClaims> {
+    let auth_cookie = cookies.get("authToken")?;
+
+    decode::<AuthClaims>(
+        auth_cookie.value(),
+        &DecodingKey::from_secret(SECRET.lock().await.expose()),
+        &Validation::default(),
+    )
+    .ok()
+    .map(|token| token.claims)
+}
+
+// Re-issues the auth token of a valid session once it is older than session_refresh_secs
+// so a session only expires after session_lifetime_secs of inactivity
+async fn session_refresh_middleware(
+    cookies: CookieJar,
+    req: Request,
+    next: Next,
+) -> Response<Body> {
+    let mut response = next.run(req).await;
+
+    // Don't undo a response that replaced or cleared the cookie itself
+    if response.headers().contains_key(SET_COOKIE) {
+        return response;
+    }
+
+    if let Some(claims) = verify_auth_cookie(&cookies).await {
+        if unix_now().saturating_sub(claims.iat as u64) >= *SESSION_REFRESH_SECS.lock().await {
+            debug!("SESSION: refreshing token for {}", claims.un);
+            let auth_cookie = issue_auth_cookie(claims.sub, claims.un, claims.csrf).await;
+            response
+                .headers_mut()
+                .append(SET_COOKIE, auth_cookie.to_string().parse().unwrap());
+        }
+    }
+
+    response
+}
+
+// Generates the CSRF token of a new session
+fn generate_csrf_token() -> String {
+    let mut token = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut token);
+    BASE64_URL_SAFE_NO_PAD.encode(token)
+}
+
+// Compares two tokens without leaking how much of them matched through timing
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Answers a request that needs a session, htmx requests are sent to the login page
+fn login_required_response() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header("HX-Redirect", "/login")
+        .body(Body::empty())
+        .unwrap()
+}
+
+// Guards the routes that read private data, like the team library export, with the session the dashboard pages need
+async fn session_middleware(cookies: CookieJar, req: Request, next: Next) -> Response<Body> {
+    if verify_auth_cookie(&cookies).await.is_none() {
+        debug!(
+            "BLOCK: {} {} without a valid session",
+            req.method(),
+            req.uri().path()
+        );
+        return login_required_response();
+    }
+
+    next.run(req).await
+}
+
+// Guards every state changing route: the request needs a valid session and has to echo the
+// session's CSRF token in the X-CSRF-Token header, which other sites can't read or set
+async fn csrf_middleware(
+    ConnectInfo(client_addr): ConnectInfo<SocketAddr>,
+    cookies: CookieJar,
+    mut req: Request,
+    next: Next,
+) -> Response<Body> {
+    let Some(claims) = verify_auth_cookie(&cookies).await else {
+        debug!(
+            "BLOCK: {} {} without a valid session",
+            req.method(),
+            req.uri().path()
+        );
+        return login_required_response();
+    };
+
+    let csrf_header = req
+        .headers()
+        .get(CSRF_HEADER)
+        .map(|x| x.as_bytes())
+        .unwrap_or_default();
+
+    if !tokens_match(csrf_header, claims.csrf.as_bytes()) {
+        warn!(
+            target: SECURITY_LOG_TARGET,
+            addr = %client_addr.ip(),
+            username = claims.un,
+            path = req.uri().path(),
+            "CSRF: missing or invalid token"
+        );
+        return Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(Body::from("Missing or invalid CSRF token"))
+            .unwrap();
+    }
+
+    req.extensions_mut().insert(claims);
+    next.run(req).await
+}
+
+// Generates a new random signing key
+fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+// Writes the signing key to disk and makes it the active key
+async fn store_secret(secret: Vec<u8>) {
+    tokio::fs::write(SECRET_FILE, BASE64_STANDARD.encode(&secret))
+        .await
+        .unwrap();
+    *SECRET.lock().await = Redacted(secret);
+}
+
+// Loads the signing key once at startup, creating (or upgrading a too short) key if needed
+async fn load_or_create_secret() {
+    let secret = match tokio::fs::read_to_string(SECRET_FILE).await {
+        Ok(contents) => BASE64_STANDARD.decode(contents.trim()).ok(),
+        Err(_) => None,
+    };
+
+    match secret {
+        Some(secret) if secret.len() >= SECRET_LEN => {
+            *SECRET.lock().await = Redacted(secret);
+        }
+        Some(_) => {
+            warn!(
+                "SECRET: signing key is too short, replacing it (existing sessions are logged out)"
+            );
+            store_secret(generate_secret()).await;
+        }
+        None => {
+            if Path::new(SECRET_FILE).exists() {
+                warn!("SECRET: signing key is in an old format, replacing it (existing sessions are logged out)");
+            } else {
+                info!("CREATE: {}", SECRET_FILE);
+            }
+            store_secret(generate_secret()).await;
+        }
     }
 }
 
+// Replaces the signing key, which invalidates every issued session
+async fn rotate_secret_handler(Extension(claims): Extension<AuthClaims>) -> impl IntoResponse {
+    store_secret(generate_secret()).await;
+    warn!(target: SECURITY_LOG_TARGET, username = claims.un, "SECRET: rotated, all sessions logged out");
+
+    // Sends the browser back to the login page and drops the now useless cookies
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("HX-Redirect", "/login")
+        .header(
+            SET_COOKIE,
+            Cookie::build(("authToken", ""))
+                .path("/")
+                .removal()
+                .build()
+                .to_string(),
+        )
+        .header(
+            SET_COOKIE,
+            Cookie::build((CSRF_COOKIE, ""))
+                .path("/")
+                .removal()
+                .build()
+                .to_string(),
+        )
+        .body(Body::empty())
+        .unwrap()
+        .into_response()
+}
+
 // endregion: --- Login fn's
 // region: --- Misc handelers
 
 // Function for testing http requests
 //async fn test_handler() {
-//    println!(" -> TEST: test");
+//    info!("TEST: test");
 //}
 
-// Handles and returns the chromakey color as a css background color
-async fn chromargb_handler() -> Html<String> {
-    let chromakey = CHROMAKEY.lock().await;
-    Html(format!(
-        "<style>body {{ background-color: rgb({}, {}, {}); }}</style>",
-        chromakey.0, chromakey.1, chromakey.2
-    ))
-}
-
-// Handles and returns the score as a string formatted for the scoreboard
+// Handles and returns the score formatted for the scoreboard, its text is still "home - away"
 async fn score_handler() -> Html<String> {
     let home_points = HOME_POINTS.lock().await;
     let away_points = AWAY_POINTS.lock().await;
-    Html(format!("{} - {}", home_points, away_points))
+    Html(format!(
+        "<span class=\"home-score\">{}</span><span class=\"score-separator\"> - </span><span class=\"away-score\">{}</span>",
+        home_points, away_points
+    ))
 }
 
 // Handles and returns the time and quarter as a string formatted for the scoreboard
@@ -1249,26 +8240,35 @@ async fn time_and_quarter_handler() -> Html<String> {
     let show_quarter = SHOW_QUARTER.lock().await;
     if *show_quarter {
         if *quarter == 1 {
-            return Html(format!("{}:{:02?} - 1st", time_mins, time_secs));
+            Html(format!("{}:{:02?} - 1st", time_mins, time_secs))
         } else if *quarter == 2 {
-            return Html(format!("{}:{:02?} - 2nd", time_mins, time_secs));
+            Html(format!("{}:{:02?} - 2nd", time_mins, time_secs))
         } else if *quarter == 3 {
-            return Html(format!("{}:{:02?} - 3rd", time_mins, time_secs));
+            Html(format!("{}:{:02?} - 3rd", time_mins, time_secs))
         } else if *quarter == 4 {
-            return Html(format!("{}:{:02?} - 4th", time_mins, time_secs));
+            Html(format!("{}:{:02?} - 4th", time_mins, time_secs))
         } else {
-            return Html(format!("{}:{:02?} - OT", time_mins, time_secs));
+            Html(format!("{}:{:02?} - OT", time_mins, time_secs))
         }
     } else {
-        return Html(format!("{}:{:02?}", time_mins, time_secs));
+        Html(format!("{}:{:02?}", time_mins, time_secs))
     }
 }
 
 async fn reset_scoreboard_handler() {
-    println!(" -> SCOREBOARD: reset");
+    info!("SCOREBOARD: reset");
     *HOME_NAME.lock().await = String::from("team_name");
     *AWAY_NAME.lock().await = String::from("team_name");
 
+    *HOME_COLORS.lock().await = TeamColors::default();
+    *AWAY_COLORS.lock().await = TeamColors::default();
+
+    *HOME_TEAM_ID.lock().await = None;
+    *AWAY_TEAM_ID.lock().await = None;
+
+    GAME_EVENTS.lock().await.clear();
+    *GAME_STARTED_AT.lock().await = None;
+
     *HOME_POINTS.lock().await = 0;
     *AWAY_POINTS.lock().await = 0;
 
@@ -1279,108 +8279,757 @@ async fn reset_scoreboard_handler() {
 
     *QUARTER.lock().await = 1;
 
-    *COUNTDOWN_TITLE.lock().await = String::from("countdown_title");
+    clear_popups().await;
 
-    *COUNTDOWN_MINS.lock().await = 0;
-    *COUNTDOWN_SECS.lock().await = 0;
-    *COUNTDOWN_STARTED.lock().await = false;
+    // Timers are stopped and cleared, countdowns to a time keep counting
+    for countdown in COUNTDOWNS.lock().await.iter_mut() {
+        if let CountdownTarget::Duration { secs } = &mut countdown.target {
+            *secs = 0;
+            countdown.started = false;
+            countdown.completed = false;
+        }
+    }
 }
 
-async fn popup_handler(axum::extract::Path(popup_type): axum::extract::Path<String>) {
-    match popup_type.as_str() {
-        "timeout" => {
-            let mut timeout = TIMEOUT.lock().await;
-            if *timeout == false {
-                println!(" -> TIMEOUT");
-                *timeout = true;
-                drop(timeout);
-                sleep(Duration::from_secs(4)).await;
-                *TIMEOUT.lock().await = false;
+// endregion: -- Sponsor roll
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_attempts_back_off_and_lock_out() {
+        let now = Instant::now();
+        let lockout = Duration::from_secs(900);
+        let mut attempts = LoginAttempts::new(now);
+        assert_eq!(attempts.retry_after(now), None);
+
+        // The delay doubles with every failure
+        assert!(!attempts.record_failure(now, 5, lockout));
+        assert_eq!(attempts.retry_after(now), Some(Duration::from_secs(1)));
+        assert!(!attempts.record_failure(now, 5, lockout));
+        assert_eq!(attempts.retry_after(now), Some(Duration::from_secs(2)));
+        assert_eq!(attempts.retry_after(now + Duration::from_secs(2)), None);
+
+        assert!(!attempts.record_failure(now, 5, lockout));
+        assert!(!attempts.record_failure(now, 5, lockout));
+        assert!(attempts.record_failure(now, 5, lockout));
+        assert_eq!(attempts.retry_after(now), Some(lockout));
+        assert!(!attempts.is_stale(now + lockout, lockout));
+        assert!(attempts.is_stale(now + lockout * 2, lockout));
+    }
+
+    #[test]
+    fn login_attempts_allow_one_password_check_at_a_time() {
+        let now = Instant::now();
+        let mut attempts = LoginAttempts::new(now);
+
+        attempts.verifying_since = Some(now);
+        assert_eq!(attempts.retry_after(now), Some(Duration::from_secs(1)));
+        assert!(!attempts.is_stale(now + Duration::from_secs(5), Duration::ZERO));
+
+        // A check that never finished stops blocking after the timeout
+        let later = now + Duration::from_secs(LOGIN_VERIFY_TIMEOUT_SECS);
+        assert_eq!(attempts.retry_after(later), None);
+
+        attempts.record_failure(now, 5, Duration::from_secs(900));
+        assert_eq!(attempts.verifying_since, None);
+    }
+
+    #[test]
+    fn tokens_match_needs_the_same_bytes() {
+        let token = generate_csrf_token();
+        assert!(tokens_match(token.as_bytes(), token.clone().as_bytes()));
+        assert!(tokens_match(b"", b""));
+
+        assert!(!tokens_match(
+            token.as_bytes(),
+            generate_csrf_token().as_bytes()
+        ));
+        assert!(!tokens_match(b"abcdef", b"abcdeg"));
+        assert!(!tokens_match(b"abcdef", b"abcde"));
+        assert!(!tokens_match(b"abc", b"abcdef"));
+        assert!(!tokens_match(b"", token.as_bytes()));
+    }
+
+    // Folder name, teaminfo.json and the other files of a team folder
+    type TeamFixture<'a> = (&'a str, &'a str, &'a [(&'a str, &'a [u8])]);
+
+    // Writes a teams folder with a teaminfo.json and the files of each entry, like the old presets were saved
+    fn legacy_teams_fixture(teams: &[TeamFixture]) -> String {
+        let dir = std::env::temp_dir()
+            .join(format!("froggi-test-{}", Uuid::new_v4().simple()))
+            .to_string_lossy()
+            .to_string();
+
+        for (id, info, files) in teams {
+            std::fs::create_dir_all(format!("{}/{}", dir, id)).unwrap();
+            std::fs::write(format!("{}/{}/teaminfo.json", dir, id), info).unwrap();
+            for (name, data) in *files {
+                std::fs::write(format!("{}/{}/{}", dir, id, name), data).unwrap();
             }
         }
-        "foul_home" => {
-            let mut foul_home = FOUL_HOME.lock().await;
-            if *foul_home == false {
-                println!(" -> FOUL: home");
-                *foul_home = true;
-                drop(foul_home);
-                sleep(Duration::from_secs(4)).await;
-                *FOUL_HOME.lock().await = false;
-            }
+        dir
+    }
+
+    #[tokio::test]
+    async fn migrate_legacy_presets_merges_only_identical_teams() {
+        let logo: &[u8] = b"\x89PNG tiger";
+        let dir = legacy_teams_fixture(&[
+            (
+                "presetA",
+                r##"{"home_name":"Tigers","home_color":"#ff0000","away_name":"Bears","away_color":"#0000ff"}"##,
+                &[("home.png", logo)],
+            ),
+            (
+                "presetB",
+                r##"{"home_name":"Tigers","home_color":"#ff0000","away_name":"Bears","away_color":"#00ff00"}"##,
+                &[("home.png", logo)],
+            ),
+            (
+                "presetC",
+                r##"{"home_name":"Lions","home_color":"#ffff00","away_name":"Owls","away_color":"#000000"}"##,
+                &[],
+            ),
+            (
+                "library",
+                r##"{"name":"Bears","abbreviation":"BEA","primary_color":"#0000ff","secondary_color":"#ffffff"}"##,
+                &[],
+            ),
+        ]);
+
+        // presetC was migrated by a start that stopped before removing it
+        let mut matches = vec![SavedMatch {
+            id: String::from("presetC"),
+            home: String::from("library"),
+            away: String::from("library"),
+        }];
+        let mut migrated = migrate_legacy_presets(&dir, &mut matches).await;
+        migrated.sort();
+        assert_eq!(migrated, ["presetA", "presetB", "presetC"]);
+        assert_eq!(matches.len(), 3);
+
+        let a = matches.iter().find(|m| m.id == "presetA").unwrap().clone();
+        let b = matches.iter().find(|m| m.id == "presetB").unwrap().clone();
+        // Same name, color and logo is the same team, the same name in another color isn't
+        assert_eq!(a.home, b.home);
+        assert_eq!(a.away, "library");
+        assert_ne!(b.away, "library");
+
+        let home: TeamInfoContainer = serde_json::from_str(
+            &std::fs::read_to_string(format!("{}/{}/teaminfo.json", dir, a.home)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(home.name, "Tigers");
+        assert_eq!(home.abbreviation, "TIG");
+        assert_eq!(
+            std::fs::read(format!("{}/{}/logo.png", dir, a.home)).unwrap(),
+            logo
+        );
+
+        // The presets are left for migrate_team_presets to remove once the matches are saved
+        assert!(Path::new(&format!("{}/presetA/teaminfo.json", dir)).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_team_text_trims_and_limits() {
+        let team = |name: &str, abbreviation: &str| TeamInfoContainer {
+            name: name.to_string(),
+            abbreviation: abbreviation.to_string(),
+            primary_color: String::from("#000000"),
+            secondary_color: String::from("#ffffff"),
+        };
+
+        let mut info = team("  Swamp Toads ", " ");
+        assert!(validate_team_text(&mut info).is_ok());
+        assert_eq!(
+            (info.name.as_str(), info.abbreviation.as_str()),
+            ("Swamp Toads", "ST")
+        );
+
+        for (name, abbreviation) in [
+            ("", "ST"),
+            (" \t ", "ST"),
+            (&"x".repeat(TEAM_FORM_TEXT_MAX + 1), "ST"),
+            ("Toads", &"x".repeat(TEAM_FORM_TEXT_MAX + 1)),
+        ] {
+            assert_eq!(
+                validate_team_text(&mut team(name, abbreviation)),
+                Err(StatusCode::BAD_REQUEST)
+            );
         }
-        "foul_away" => {
-            let mut foul_away = FOUL_AWAY.lock().await;
-            if *foul_away == false {
-                println!(" -> FOUL: away");
-                *foul_away = true;
-                drop(foul_away);
-                sleep(Duration::from_secs(4)).await;
-                *FOUL_AWAY.lock().await = false;
-            }
+    }
+
+    #[test]
+    fn parse_color_accepts_every_written_form() {
+        assert_eq!(parse_color("#ff8000"), Some((255, 128, 0)));
+        assert_eq!(parse_color("#F80"), Some((255, 136, 0)));
+        assert_eq!(parse_color(" rgb(1, 2, 3) "), Some((1, 2, 3)));
+        assert_eq!(parse_color("0, 177, 64"), Some((0, 177, 64)));
+
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("#gg0000"), None);
+        assert_eq!(parse_color("#ééé"), None);
+        assert_eq!(parse_color("256, 0, 0"), None);
+        assert_eq!(parse_color("1, 2"), None);
+        assert_eq!(parse_color("red"), None);
+        assert_eq!(parse_color(""), None);
+    }
+
+    #[test]
+    fn normalize_color_writes_hex() {
+        assert_eq!(normalize_color("#FFF").as_deref(), Some("#ffffff"));
+        assert_eq!(
+            normalize_color("rgb(0, 177, 64)").as_deref(),
+            Some("#00b140")
+        );
+        assert_eq!(normalize_color("blue"), None);
+    }
+
+    #[test]
+    fn contrast_text_color_picks_the_readable_one() {
+        assert_eq!(contrast_text_color((255, 255, 255)), (0, 0, 0));
+        assert_eq!(contrast_text_color((255, 255, 0)), (0, 0, 0));
+        assert_eq!(contrast_text_color((0, 0, 0)), (255, 255, 255));
+        assert_eq!(contrast_text_color((0, 0, 255)), (255, 255, 255));
+        assert_eq!(contrast_text_color((128, 0, 0)), (255, 255, 255));
+    }
+
+    // Encodes a plain red image of the given size
+    fn encoded_image(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut bytes, format)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn image_kind_is_detected_from_the_magic_bytes() {
+        let png = encoded_image(2, 2, image::ImageFormat::Png);
+        let jpeg = encoded_image(2, 2, image::ImageFormat::Jpeg);
+        let webp = encoded_image(2, 2, image::ImageFormat::WebP);
+
+        assert_eq!(ImageKind::detect(&png), Some(ImageKind::Png));
+        assert_eq!(ImageKind::detect(&jpeg), Some(ImageKind::Jpeg));
+        assert_eq!(ImageKind::detect(&webp), Some(ImageKind::WebP));
+        assert_eq!(
+            ImageKind::detect(
+                b"\xef\xbb\xbf  <?xml version=\"1.0\"?><svg xmlns=\"http://www.w3.org/2000/svg\"/>"
+            ),
+            Some(ImageKind::Svg)
+        );
+
+        assert_eq!(ImageKind::detect(b""), None);
+        assert_eq!(ImageKind::detect(b"GIF89a"), None);
+        assert_eq!(ImageKind::detect(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(
+            ImageKind::detect(b"<html><body>no svg here</body></html>"),
+            None
+        );
+        assert_eq!(ImageKind::detect(b"logo.svg"), None);
+    }
+
+    #[test]
+    fn normalize_image_converts_and_downscales() {
+        let decode = |bytes: &[u8]| {
+            image::load_from_memory_with_format(bytes, image::ImageFormat::Png).unwrap()
+        };
+
+        // A small png is kept byte for byte
+        let png = encoded_image(8, 4, image::ImageFormat::Png);
+        assert_eq!(
+            normalize_image(png.clone(), 16),
+            Some((ImageKind::Png, png))
+        );
+
+        // Jpeg and webp become png
+        for format in [image::ImageFormat::Jpeg, image::ImageFormat::WebP] {
+            let (kind, bytes) = normalize_image(encoded_image(8, 4, format), 16).unwrap();
+            assert_eq!(kind, ImageKind::Png);
+            let img = decode(&bytes);
+            assert_eq!((img.width(), img.height()), (8, 4));
         }
-        "flag" => {
-            let mut flag = FLAG.lock().await;
-            if *flag == false {
-                println!(" -> FLAG");
-                *flag = true;
-                drop(flag);
-                sleep(Duration::from_secs(4)).await;
-                *FLAG.lock().await = false;
-            }
+
+        // Anything larger than the max size is scaled down, keeping its aspect ratio
+        let (kind, bytes) =
+            normalize_image(encoded_image(64, 32, image::ImageFormat::Png), 16).unwrap();
+        assert_eq!(kind, ImageKind::Png);
+        let img = decode(&bytes);
+        assert_eq!((img.width(), img.height()), (16, 8));
+
+        // Svg is passed through, whatever its size
+        let svg =
+            br#"<svg xmlns="http://www.w3.org/2000/svg" width="5000" height="5000"/>"#.to_vec();
+        assert_eq!(
+            normalize_image(svg.clone(), 16),
+            Some((ImageKind::Svg, svg))
+        );
+
+        // Magic bytes with a broken image behind them are refused
+        let mut broken = encoded_image(8, 4, image::ImageFormat::Png);
+        broken.truncate(20);
+        assert_eq!(normalize_image(broken, 16), None);
+        assert_eq!(normalize_image(b"not an image".to_vec(), 16), None);
+    }
+
+    #[test]
+    fn image_cache_drops_images_nobody_uses() {
+        let mut cache = ImageCache::default();
+
+        let first = cache.insert(String::from("team:a"), b"first".to_vec());
+        let shared = cache.insert(String::from("home"), b"first".to_vec());
+        assert_eq!(first, shared);
+
+        // Replacing the team's logo keeps the image the home logo still uses
+        let second = cache.insert(String::from("team:a"), b"second".to_vec());
+        assert!(cache.get(&first).is_some());
+        assert!(cache.get(&second).is_some());
+
+        cache.release("home");
+        assert!(cache.get(&first).is_none());
+        assert_eq!(cache.hash("home"), None);
+
+        cache.insert(String::from("sponsor:a.png"), b"a".to_vec());
+        let kept = cache.insert(String::from("sponsor:b.png"), b"b".to_vec());
+        cache.retain_owners(|owner| owner != "sponsor:a.png");
+        assert_eq!(cache.images.len(), 2);
+        assert!(cache.get(&kept).is_some());
+        assert!(cache.get(&second).is_some());
+    }
+
+    fn player(number: &str, name: &str) -> Player {
+        Player {
+            number: number.to_string(),
+            name: name.to_string(),
+            position: String::new(),
         }
-        _ => {}
     }
-}
 
-async fn popup_show_handler() -> Html<String> {
-    let mut html = String::new();
-    let style = "style=\"color: black; text-align: center; display: flex; justify-content: center; align-items: center; height: 100%; font-family: 'Protest Strike', cursive; font-size: 24px;\"";
-    if *TIMEOUT.lock().await {
-        html += &format!("<p {}>Timeout</p>", style);
+    #[test]
+    fn validate_roster_needs_unique_numbers_and_names() {
+        assert!(validate_roster(&[]).is_ok());
+        assert!(
+            validate_roster(&[player("0", "Ann"), player("99", "Bo"), player("100", "Cy")]).is_ok()
+        );
+
+        assert!(validate_roster(&[player("", "Ann")]).is_err());
+        assert!(validate_roster(&[player("1000", "Ann")]).is_err());
+        assert!(validate_roster(&[player("-1", "Ann")]).is_err());
+        assert!(validate_roster(&[player("12a", "Ann")]).is_err());
+        assert!(validate_roster(&[player("7", " ")]).is_err());
+        assert!(validate_roster(&[player("7", "Ann"), player("7", "Bo")]).is_err());
+    }
+
+    #[test]
+    fn parse_roster_csv_skips_the_header_and_blank_rows() {
+        let csv = b"number,name,position\n 7 , Ann Lee ,Guard\n\n23,Bo\n,,\n";
+        let roster: Vec<(String, String, String)> = parse_roster_csv(csv)
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.number, p.name, p.position))
+            .collect();
+
+        assert_eq!(
+            roster,
+            vec![
+                (
+                    String::from("7"),
+                    String::from("Ann Lee"),
+                    String::from("Guard")
+                ),
+                (String::from("23"), String::from("Bo"), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_roster_csv_rejects_invalid_rows() {
+        // A first row with a valid number is a player, not a header
+        assert_eq!(parse_roster_csv(b"7,Ann\n8,Bo").unwrap().len(), 2);
+        assert!(parse_roster_csv(b"7,Ann\n7,Bo").is_err());
+        assert!(parse_roster_csv(b"number,name\nseven,Ann").is_err());
+        assert!(parse_roster_csv(b"7,").is_err());
+        assert!(parse_roster_csv(b"").unwrap().is_empty());
+    }
+
+    // Zips files the way the export routes do
+    fn zip_files(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (path, data) in files {
+            writer.start_file(*path, options).unwrap();
+            io::Write::write_all(&mut writer, data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
     }
-    if *FOUL_HOME.lock().await {
-        html += &format!("<p {}>Foul: Home</p>", style);
+
+    fn bundle_limits(max_file_size: u64, max_total_size: u64) -> BundleLimits {
+        BundleLimits {
+            max_file_size,
+            max_total_size,
+            max_entries: 10,
+        }
     }
-    if *FOUL_AWAY.lock().await {
-        html += &format!("<p {}>Foul: Away</p>", style);
+
+    #[test]
+    fn read_bundle_reads_teams_and_matches() {
+        let zip = zip_files(&[
+            ("matches.json", b"[]"),
+            ("abc/teaminfo.json", b"{}"),
+            ("abc/logo.svg", b"<svg/>"),
+            ("abc/headshots/7.png", b"png"),
+            ("def/teaminfo.json", b"{}"),
+            ("def/roster.json", b"[]"),
+        ]);
+        let bundle = read_bundle(&zip, &bundle_limits(100, 1000)).unwrap();
+
+        assert_eq!(bundle.matches.as_deref(), Some(&b"[]"[..]));
+        assert_eq!(bundle.teams.len(), 2);
+        let (dir, abc) = &bundle.teams[0];
+        assert_eq!(dir, "abc");
+        assert_eq!(abc.teaminfo.as_deref(), Some(&b"{}"[..]));
+        assert_eq!(
+            abc.logo,
+            Some((String::from("logo.svg"), b"<svg/>".to_vec()))
+        );
+        assert_eq!(
+            abc.headshots,
+            vec![(String::from("7.png"), b"png".to_vec())]
+        );
+        assert_eq!(bundle.teams[1].1.roster.as_deref(), Some(&b"[]"[..]));
     }
-    if *FLAG.lock().await {
-        html += &format!("<p {}>Flag on the play</p>", style);
+
+    #[test]
+    fn read_bundle_rejects_unknown_files() {
+        let limits = bundle_limits(100, 1000);
+        assert!(read_bundle(b"not a zip", &limits).is_none());
+        assert!(read_bundle(&zip_files(&[("abc/notes.txt", b"")]), &limits).is_none());
+        assert!(read_bundle(&zip_files(&[("teaminfo.json", b"{}")]), &limits).is_none());
+        assert!(read_bundle(&zip_files(&[("../abc/teaminfo.json", b"{}")]), &limits).is_none());
+        assert!(read_bundle(
+            &zip_files(&[("abc/logo.png", b"a"), ("abc/logo.svg", b"b")]),
+            &limits
+        )
+        .is_none());
     }
-    return Html(html);
-}
 
-// endregion: -- Sponsor roll
-// region: --- Misc fn's
+    #[test]
+    fn read_bundle_stops_at_the_limits() {
+        let big = vec![0u8; 600];
+        let limits = bundle_limits(1000, 1000);
 
-async fn secret_file_verifier() {
-    match tokio::fs::File::open("login/secrets.txt").await {
-        Ok(_) => {}
-        Err(_) => {
-            let secret: String = rand::thread_rng()
-                .sample_iter(&Alphanumeric)
-                .take(30)
-                .map(char::from)
-                .collect();
+        // Each file is small enough, together they unpack to more than the total budget
+        let bomb = zip_files(&[("abc/teaminfo.json", &big), ("def/teaminfo.json", &big)]);
+        assert!(bomb.len() < 600);
+        assert!(read_bundle(&bomb, &limits).is_none());
+        assert!(read_bundle(&bomb, &bundle_limits(1000, 1200)).is_some());
+
+        assert!(read_bundle(
+            &zip_files(&[("abc/teaminfo.json", &big)]),
+            &bundle_limits(599, 1000)
+        )
+        .is_none());
 
-            let mut key_file = tokio::fs::File::create("login/secrets.txt").await.unwrap();
-            key_file.write(secret.to_string().as_bytes()).await.unwrap();
+        let names: Vec<String> = (0..11).map(|i| format!("t{}/teaminfo.json", i)).collect();
+        let many: Vec<(&str, &[u8])> = names.iter().map(|n| (n.as_str(), &b"{}"[..])).collect();
+        assert!(read_bundle(&zip_files(&many), &limits).is_none());
+        assert!(read_bundle(&zip_files(&many[..10]), &limits).is_some());
+    }
 
-            println!(" -> CREATE: secrets.txt");
+    fn score_event(period: u8, side: &str, points: i32) -> GameEvent {
+        GameEvent {
+            at: 0,
+            period,
+            clock: String::new(),
+            side: side.to_string(),
+            action: GameAction::Score {
+                kind: ScoreKind::Points,
+                points,
+                player: None,
+                assist: None,
+            },
         }
     }
-}
 
-async fn popup_css_handler() -> Html<&'static str> {
-    if !*FOUL_AWAY.lock().await
-        && !*FOUL_HOME.lock().await
-        && !*FLAG.lock().await
-        && !*TIMEOUT.lock().await
-    {
-        return Html("<style> .popup-container { display: none; } </style>");
-    } else {
-        return Html("");
+    #[test]
+    fn period_scores_keeps_scoreless_periods() {
+        let events = vec![
+            score_event(1, "home", 3),
+            score_event(3, "away", 2),
+            score_event(1, "away", 1),
+            GameEvent {
+                at: 0,
+                period: 2,
+                clock: String::new(),
+                side: String::from("home"),
+                action: GameAction::Foul { player: None },
+            },
+        ];
+
+        let periods: Vec<(u8, i32, i32)> = period_scores(&events, 4)
+            .iter()
+            .map(|p| (p.period, p.home, p.away))
+            .collect();
+        assert_eq!(periods, vec![(1, 3, 1), (2, 0, 0), (3, 0, 2), (4, 0, 0)]);
+
+        // Periods with events after the current one are still counted
+        assert_eq!(period_scores(&events, 1).len(), 3);
+        assert_eq!(period_scores(&[], 0).len(), 1);
     }
-}
 
-// endregion: -- Misc fn's
+    #[test]
+    fn period_label_numbers_the_overtimes() {
+        assert_eq!(period_label(4), "4th");
+        assert_eq!(period_label(5), "OT");
+        assert_eq!(period_label(6), "2OT");
+        assert_eq!(period_label(7), "3OT");
+        assert_eq!(period_label(u8::MAX), "251OT");
+        assert_eq!(period_label(0), "1st");
+    }
+
+    fn sponsor(file: &str, weight: u32) -> Sponsor {
+        let mut entry = SponsorEntry::new(file.to_string());
+        entry.weight = weight;
+        Sponsor {
+            entry,
+            url: String::new(),
+            file_state: (0, None),
+        }
+    }
+
+    #[test]
+    fn pick_sponsor_follows_the_weights() {
+        let sponsors = vec![
+            sponsor("a.png", 3),
+            sponsor("b.png", 1),
+            sponsor("c.png", 1),
+        ];
+        let mut weights = HashMap::new();
+
+        let picks: Vec<String> = (0..10)
+            .map(|_| {
+                pick_sponsor(&sponsors, &mut weights)
+                    .unwrap()
+                    .entry
+                    .file
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            picks,
+            [
+                "a.png", "b.png", "a.png", "c.png", "a.png", "a.png", "b.png", "a.png", "c.png",
+                "a.png"
+            ]
+        );
+
+        // A sponsor that isn't eligible anymore loses its turn
+        pick_sponsor(&sponsors[1..], &mut weights);
+        assert!(!weights.contains_key("a.png"));
+        assert!(pick_sponsor(&[], &mut weights).is_none());
+    }
+
+    #[test]
+    fn sponsor_settings_are_clamped() {
+        let mut entry = SponsorEntry::new(String::from("a.png"));
+        assert!(!entry.clamp_settings());
+
+        entry.duration_secs = 0;
+        entry.weight = 0;
+        assert!(entry.clamp_settings());
+        assert_eq!((entry.duration_secs, entry.weight), (1, 1));
+
+        entry.duration_secs = u64::MAX;
+        entry.weight = u32::MAX;
+        assert!(entry.clamp_settings());
+        assert_eq!(
+            (entry.duration_secs, entry.weight),
+            (SPONSOR_MAX_DURATION_SECS, SPONSOR_MAX_WEIGHT)
+        );
+    }
+
+    #[test]
+    fn countdown_duration_rejects_overflow() {
+        assert_eq!(countdown_duration(2, 30), Some(150));
+        assert_eq!(
+            countdown_duration(u64::MAX / 60, 0),
+            Some(u64::MAX / 60 * 60)
+        );
+        assert_eq!(countdown_duration(u64::MAX / 60 + 1, 0), None);
+        assert_eq!(countdown_duration(u64::MAX / 60, 60), None);
+    }
+
+    #[test]
+    fn parse_countdown_time_accepts_times_and_dates() {
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(18, 30, 0)
+            .unwrap();
+        let today = |h, m, s| now.date().and_hms_opt(h, m, s).unwrap();
+
+        assert_eq!(parse_countdown_time("19:00", now), Some(today(19, 0, 0)));
+        assert_eq!(
+            parse_countdown_time("19:00:15", now),
+            Some(today(19, 0, 15))
+        );
+        // A time earlier today isn't moved to tomorrow, the countdown just shows 0:00
+        assert_eq!(parse_countdown_time("08:05", now), Some(today(8, 5, 0)));
+        assert_eq!(
+            parse_countdown_time("2024-03-10T09:15", now),
+            chrono::NaiveDate::from_ymd_opt(2024, 3, 10)
+                .unwrap()
+                .and_hms_opt(9, 15, 0)
+        );
+
+        for at in ["", "25:00", "7pm", "19:60", "2024-13-01T10:00"] {
+            assert_eq!(parse_countdown_time(at, now), None, "{}", at);
+        }
+    }
+
+    #[test]
+    fn time_countdown_finishes_again_the_next_day() {
+        let mut countdown = Countdown::new(
+            String::from("doors"),
+            CountdownTarget::Time {
+                at: String::from("19:00"),
+            },
+            CountdownAction::Message {
+                text: String::from("Doors open"),
+            },
+        );
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let at = |day: chrono::NaiveDate, h, m, s| day.and_hms_opt(h, m, s).unwrap();
+
+        assert!(!countdown.tick(at(day, 18, 59, 59)));
+        assert!(countdown.tick(at(day, 19, 0, 0)));
+        assert!(!countdown.tick(at(day, 19, 0, 1)));
+        assert!(!countdown.tick(at(day, 23, 59, 59)));
+        assert!(countdown.completed);
+
+        let next_day = day.succ_opt().unwrap();
+        assert!(!countdown.tick(at(next_day, 0, 0, 0)));
+        assert!(!countdown.completed);
+        assert!(countdown.tick(at(next_day, 19, 0, 0)));
+    }
+
+    #[test]
+    fn duration_countdown_only_runs_while_started() {
+        let mut countdown = Countdown::new(
+            String::from("break"),
+            CountdownTarget::Duration { secs: 2 },
+            CountdownAction::None,
+        );
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        assert!(!countdown.tick(now));
+        countdown.started = true;
+        assert!(!countdown.tick(now));
+        assert!(countdown.tick(now));
+        assert!(!countdown.started);
+        assert!(!countdown.tick(now));
+    }
+
+    #[test]
+    fn parse_popup_config_reads_every_field() {
+        let popup = parse_popup_config("goal | GOAL {team}! | home | 8 | team | 7").unwrap();
+        assert_eq!(
+            (popup.name.as_str(), popup.text.as_str()),
+            ("goal", "GOAL {team}!")
+        );
+        assert!(popup.team == Some(TeamSide::Home));
+        assert_eq!(popup.duration_secs, 8);
+        assert!(popup.style == PopupStyle::Team);
+        assert_eq!(popup.priority, 7);
+
+        // Everything after the text is optional, empty fields keep their default
+        let popup = parse_popup_config("flag|Flag on the play").unwrap();
+        assert!(popup.team.is_none() && popup.style == PopupStyle::Default);
+        assert_eq!(
+            (popup.duration_secs, popup.priority),
+            (default_popup_duration(), 0)
+        );
+        let popup = parse_popup_config("injury|Injury timeout||||3").unwrap();
+        assert!(popup.team.is_none());
+        assert_eq!(popup.priority, 3);
+
+        for value in [
+            "",
+            "name_only",
+            "goal|",
+            "Goal|Bad name",
+            "goal|Goal|both",
+            "goal|Goal||soon",
+            "goal|Goal||0",
+            "goal|Goal||61",
+            "goal|Goal|||blink",
+            "goal|Goal||||256",
+        ] {
+            assert!(parse_popup_config(value).is_none(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn next_popup_index_takes_the_highest_priority_first() {
+        let queued = |name: &str, priority: u8| QueuedPopup {
+            name: name.to_string(),
+            text: name.to_string(),
+            team: None,
+            duration_secs: 4,
+            style: PopupStyle::Default,
+            priority,
+        };
+
+        let mut queue = vec![
+            queued("flag", 5),
+            queued("goal", 0),
+            queued("timeout", 10),
+            queued("foul_home", 5),
+        ];
+        let mut order = Vec::new();
+        while let Some(i) = next_popup_index(&queue) {
+            order.push(queue.remove(i).name);
+        }
+        assert_eq!(order, ["timeout", "flag", "foul_home", "goal"]);
+    }
+
+    #[tokio::test]
+    async fn render_size_refuses_sizes_that_cant_be_drawn() {
+        let size = |width, height| RenderQuery {
+            width: Some(width),
+            height: Some(height),
+        };
+
+        assert_eq!(render_size(&size(1280, 72)).await, Ok((1280, 72)));
+        assert_eq!(render_size(&size(100, 20)).await, Ok((100, 20)));
+        assert_eq!(
+            render_size(&size(RENDER_MAX_WIDTH, RENDER_MAX_HEIGHT)).await,
+            Ok((RENDER_MAX_WIDTH, RENDER_MAX_HEIGHT))
+        );
+
+        for (width, height) in [
+            (99, 72),
+            (1280, 19),
+            (RENDER_MAX_WIDTH + 1, 72),
+            (1280, RENDER_MAX_HEIGHT + 1),
+            // The gaps alone would be wider than the image
+            (100, 2160),
+            (500, 1200),
+        ] {
+            assert_eq!(
+                render_size(&size(width, height)).await,
+                Err(StatusCode::BAD_REQUEST),
+                "{}x{}",
+                width,
+                height
+            );
+        }
+    }
+}